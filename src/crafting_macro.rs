@@ -0,0 +1,150 @@
+//! Conversion between rotations and in-game crafting macros.
+//!
+//! ```text
+//! /ac "Reflect" <wait.3>
+//! /ac "Byregot's Blessing" <wait.3>
+//! /echo Macro #1 finished <se.1>
+//! ```
+
+use bon::builder;
+use std::fmt;
+
+use crate::types::{actions, enums::CraftingActionEnum, traits::CraftingAction};
+
+/// The maximum number of lines the game allows in a single macro.
+pub const MAX_MACRO_LINES: usize = 15;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MacroNotification {
+	/// `/echo Macro #n finished`
+	Echo,
+	/// `/echo Macro #n finished <se.N>`, where `N` is the sound effect (1 to 16)
+	SoundEffect(u8),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MacroParseError {
+	/// The action named on `line` (zero-indexed) doesn't exist.
+	UnknownAction { line: usize, name: String },
+}
+impl fmt::Display for MacroParseError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			MacroParseError::UnknownAction { line, name } => {
+				write!(f, "unknown action `{}` on line {}", name, line + 1)
+			}
+		}
+	}
+}
+impl std::error::Error for MacroParseError {}
+
+/// Generates the macro blocks for a rotation, split so that no block is longer than
+/// `max_lines` (which defaults to, and is capped at, [`MAX_MACRO_LINES`]).
+///
+/// When a `notification` is given, every block ends with an `/echo` line, which takes up
+/// one of the block's lines.
+#[builder]
+pub fn generate(
+	actions: &[CraftingActionEnum],
+	notification: Option<MacroNotification>,
+	max_lines: Option<usize>,
+) -> Vec<String> {
+	let max_lines = max_lines
+		.unwrap_or(MAX_MACRO_LINES)
+		.clamp(2, MAX_MACRO_LINES);
+	let actions_per_block = if notification.is_some() {
+		max_lines - 1
+	} else {
+		max_lines
+	};
+
+	actions
+		.chunks(actions_per_block)
+		.enumerate()
+		.map(|(ix, block)| {
+			let mut lines: Vec<_> = block.iter().map(action_line).collect();
+			match notification {
+				Some(MacroNotification::Echo) => {
+					lines.push(format!("/echo Macro #{} finished", ix + 1))
+				}
+				Some(MacroNotification::SoundEffect(se)) => {
+					lines.push(format!("/echo Macro #{} finished <se.{}>", ix + 1, se))
+				}
+				None => (),
+			}
+			lines.join("\n")
+		})
+		.collect()
+}
+
+/// Parses pasted macro text back into a rotation.
+///
+/// Lines that aren't action commands (`/echo`, `/mlock`, blank lines, ...) are ignored, so
+/// several blocks can be parsed at once.
+pub fn parse(text: &str) -> Result<Vec<CraftingActionEnum>, MacroParseError> {
+	let mut rotation = vec![];
+	for (line_ix, line) in text.lines().enumerate() {
+		let line = line.trim();
+		let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+		let command = command.to_lowercase();
+
+		let is_status_off = command == "/statusoff";
+		if !is_status_off && command != "/ac" && command != "/action" {
+			continue;
+		}
+
+		// drop any placeholders such as `<wait.3>` or `<se.1>`
+		let name = rest
+			.split('<')
+			.next()
+			.unwrap_or_default()
+			.trim()
+			.trim_matches(['"', '“', '”']);
+		let normalized = normalize_name(name);
+		let action = if is_status_off {
+			let final_appraisal: CraftingActionEnum = actions::FinalAppraisal.into();
+			(normalized == normalize_name(final_appraisal.game_name()))
+				.then(|| actions::RemoveFinalAppraisal.into())
+		} else {
+			CraftingActionEnum::ALL
+				.into_iter()
+				.filter(|action| *action != actions::RemoveFinalAppraisal.into())
+				.find(|action| normalize_name(action.game_name()) == normalized)
+		};
+
+		match action {
+			Some(action) => rotation.push(action),
+			None => {
+				return Err(MacroParseError::UnknownAction {
+					line: line_ix,
+					name: name.to_string(),
+				})
+			}
+		}
+	}
+	Ok(rotation)
+}
+
+fn action_line(action: &CraftingActionEnum) -> String {
+	if *action == actions::RemoveFinalAppraisal.into() {
+		format!(
+			"/statusoff \"{}\" <wait.{}>",
+			action.game_name(),
+			action.get_wait_duration()
+		)
+	} else {
+		format!(
+			"/ac \"{}\" <wait.{}>",
+			action.game_name(),
+			action.get_wait_duration()
+		)
+	}
+}
+
+// case-insensitive, and tolerant of missing or typographic apostrophes ("Byregot’s")
+fn normalize_name(name: &str) -> String {
+	name.chars()
+		.filter(|c| !matches!(c, '\'' | '’' | '‘'))
+		.flat_map(char::to_lowercase)
+		.collect()
+}
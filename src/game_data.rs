@@ -0,0 +1,363 @@
+//! Recipes loaded from the CSV sheets extracted from the game's data files.
+//!
+//! The sheets are read from a local directory, in the layout produced by the community
+//! extraction tools: a row of column indices starting with `key`, a row of column names, a
+//! row of column types, then the data. Plain CSV files with a single header row of column
+//! names are accepted as well.
+//!
+//! `Recipe.csv` and `RecipeLevelTable.csv` are required. If `Item.csv` is also present, the
+//! item level of ingredients and whether they can be HQ are read from it, which enables the
+//! item level based starting quality (see [`Craft::starting_quality`]).
+
+use std::{
+	collections::HashMap,
+	fmt,
+	io::Read,
+	path::{Path, PathBuf},
+};
+
+use crate::types::structs::{Craft, CraftingLevel, Ingredient, RecipeLevel};
+
+// the game's `CraftType` starts at Carpenter, which is job 8
+const FIRST_CRAFTER_JOB_ID: u32 = 8;
+const MAX_INGREDIENTS: usize = 10;
+
+/// Every recipe of a set of game data sheets, by recipe ID.
+#[derive(Clone, Default)]
+pub struct RecipeDatabase {
+	recipes: HashMap<u32, Craft>,
+	recipe_levels: HashMap<u32, RecipeLevel>,
+}
+impl RecipeDatabase {
+	/// Reads `Recipe.csv`, `RecipeLevelTable.csv` and, if present, `Item.csv` from `dir`.
+	pub fn load(dir: impl AsRef<Path>) -> Result<Self, GameDataError> {
+		let dir = dir.as_ref();
+		let open = |name: &str| {
+			let path = dir.join(name);
+			std::fs::File::open(&path).map_err(|err| GameDataError::Io(path, err))
+		};
+		let items = if dir.join("Item.csv").exists() {
+			Some(open("Item.csv")?)
+		} else {
+			None
+		};
+		Self::from_readers(open("Recipe.csv")?, open("RecipeLevelTable.csv")?, items)
+	}
+
+	/// Reads the sheets from already opened readers.
+	pub fn from_readers(
+		recipes: impl Read,
+		recipe_levels: impl Read,
+		items: Option<impl Read>,
+	) -> Result<Self, GameDataError> {
+		let recipe_levels = Sheet::read("RecipeLevelTable", recipe_levels)?;
+		let recipe_levels: HashMap<u32, RecipeLevel> = recipe_levels
+			.rows()
+			.map(|row| Ok((row.key()?, RecipeLevel::from_row(&row)?)))
+			.collect::<Result<_, GameDataError>>()?;
+
+		let items: HashMap<u32, (u32, bool)> = match items {
+			Some(items) => Sheet::read("Item", items)?
+				.rows()
+				.map(|row| {
+					let item_level = row.u32(&["Level{Item}", "LevelItem"])?;
+					let can_be_hq = row.bool(&["CanBeHq"])?;
+					Ok((row.key()?, (item_level, can_be_hq)))
+				})
+				.collect::<Result<_, GameDataError>>()?,
+			None => HashMap::new(),
+		};
+
+		let sheet = Sheet::read("Recipe", recipes)?;
+		let mut recipes = HashMap::new();
+		for row in sheet.rows() {
+			let id = row.key()?;
+			let result = row.u32(&["Item{Result}", "ItemResult"])?;
+			if result == 0 {
+				// unused rows are kept in the sheet with every column zeroed
+				continue;
+			}
+			let level_id = row.u32(&["RecipeLevelTable"])?;
+			let level = recipe_levels
+				.get(&level_id)
+				.ok_or(GameDataError::UnknownRecipeLevel {
+					recipe: id,
+					level: level_id,
+				})?;
+
+			let mut ingredients = vec![];
+			for ix in 0..MAX_INGREDIENTS {
+				let item_names = [
+					format!("Item{{Ingredient}}[{}]", ix),
+					format!("Ingredient[{}]", ix),
+				];
+				let amount_names = [
+					format!("Amount{{Ingredient}}[{}]", ix),
+					format!("AmountIngredient[{}]", ix),
+				];
+				let Some(item) = row.optional_u32(&item_names)? else {
+					break;
+				};
+				let amount = row.u32(&amount_names)?;
+				if item == 0 || amount == 0 {
+					continue;
+				}
+				let item_data = items.get(&item);
+				ingredients.push(Ingredient {
+					id: item.to_string(),
+					amount,
+					item_level: item_data.map(|&(item_level, _)| item_level),
+					can_be_hq: item_data.map(|&(_, can_be_hq)| can_be_hq),
+					..Default::default()
+				});
+			}
+
+			let non_zero = |value: u32| (value != 0).then_some(value);
+			let craft = Craft {
+				id: id.to_string(),
+				job: row.u32(&["CraftType"])? + FIRST_CRAFTER_JOB_ID,
+				ingredients,
+				r#yield: Some(row.u32(&["Amount{Result}", "AmountResult"])?),
+				hq: Some(row.bool(&["CanHq"])?),
+				quick_synth: Some(row.bool(&["CanQuickSynth"])?),
+				expert: Some(row.bool(&["IsExpert"])?),
+				craftsmanship_req: non_zero(row.u32(&["RequiredCraftsmanship"])?),
+				control_req: non_zero(row.u32(&["RequiredControl"])?),
+				// neither column exists in older game versions
+				required_quality: row.optional_u32(&["RequiredQuality"])?.and_then(non_zero),
+				unlock_id: row.optional_u32(&["SecretRecipeBook"])?.and_then(non_zero),
+				material_quality_factor: Some(row.u32(&["MaterialQualityFactor"])?),
+				..level.craft(
+					level_id,
+					row.u32(&["DifficultyFactor"])?,
+					row.u32(&["QualityFactor"])?,
+					row.u32(&["DurabilityFactor"])?,
+				)
+			};
+			recipes.insert(id, craft);
+		}
+
+		Ok(Self {
+			recipes,
+			recipe_levels,
+		})
+	}
+
+	pub fn get(&self, id: u32) -> Option<&Craft> {
+		self.recipes.get(&id)
+	}
+
+	/// The row of the recipe level table for `rlvl`. [`RecipeLevel::craft`] builds a recipe of
+	/// that level from its progress, quality and durability factors.
+	pub fn recipe_level(&self, rlvl: u32) -> Option<&RecipeLevel> {
+		self.recipe_levels.get(&rlvl)
+	}
+
+	pub fn len(&self) -> usize {
+		self.recipes.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.recipes.is_empty()
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (u32, &Craft)> {
+		self.recipes.iter().map(|(&id, craft)| (id, craft))
+	}
+}
+
+impl RecipeLevel {
+	fn from_row(row: &Row) -> Result<Self, GameDataError> {
+		let level = row.u32(&["ClassJobLevel"])?;
+		Ok(Self {
+			level: CraftingLevel::try_from(level.min(u8::MAX as u32) as u8).map_err(|_| {
+				GameDataError::InvalidValue {
+					sheet: row.sheet.to_string(),
+					column: "ClassJobLevel".to_string(),
+					value: level.to_string(),
+				}
+			})?,
+			stars: row.u32(&["Stars"])?,
+			suggested_craftsmanship: row.u32(&["SuggestedCraftsmanship"])?,
+			difficulty: row.u32(&["Difficulty"])?,
+			quality: row.u32(&["Quality"])?,
+			progress_divider: row.u32(&["ProgressDivider"])?,
+			quality_divider: row.u32(&["QualityDivider"])?,
+			progress_modifier: row.u32(&["ProgressModifier"])?,
+			quality_modifier: row.u32(&["QualityModifier"])?,
+			durability: row.u32(&["Durability"])?,
+			conditions_flag: row.u32(&["ConditionsFlag"])?,
+		})
+	}
+}
+
+// a whole sheet, with its column names
+struct Sheet {
+	name: &'static str,
+	columns: HashMap<String, usize>,
+	records: Vec<csv::StringRecord>,
+}
+impl Sheet {
+	fn read(name: &'static str, reader: impl Read) -> Result<Self, GameDataError> {
+		let mut reader = csv::ReaderBuilder::new()
+			.has_headers(false)
+			.flexible(true)
+			.from_reader(reader);
+		let mut records = reader
+			.records()
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(|err| GameDataError::Csv(name, err))?
+			.into_iter();
+
+		let mut header = records.next().unwrap_or_default();
+		if header.get(0) == Some("key") {
+			// skip the row of indices, and the row of types after the names
+			header = records.next().unwrap_or_default();
+			records.next();
+		}
+		let columns = header
+			.iter()
+			.enumerate()
+			.map(|(ix, column)| (column.to_string(), ix))
+			.collect();
+
+		Ok(Self {
+			name,
+			columns,
+			records: records.collect(),
+		})
+	}
+
+	fn rows(&self) -> impl Iterator<Item = Row<'_>> {
+		self.records.iter().map(move |record| Row {
+			sheet: self.name,
+			columns: &self.columns,
+			record,
+		})
+	}
+}
+
+struct Row<'a> {
+	sheet: &'static str,
+	columns: &'a HashMap<String, usize>,
+	record: &'a csv::StringRecord,
+}
+impl Row<'_> {
+	// the row ID is always the first column, whatever its header says
+	fn key(&self) -> Result<u32, GameDataError> {
+		let value = self.record.get(0).unwrap_or_default();
+		value.parse().map_err(|_| GameDataError::InvalidValue {
+			sheet: self.sheet.to_string(),
+			column: "#".to_string(),
+			value: value.to_string(),
+		})
+	}
+
+	// the value of the first of `names` that the sheet has, as different extraction tools
+	// name some columns differently
+	fn get<'n, S: AsRef<str>>(&self, names: &'n [S]) -> Option<(&'n str, &str)> {
+		names.iter().find_map(|name| {
+			let ix = self.columns.get(name.as_ref())?;
+			Some((name.as_ref(), self.record.get(*ix).unwrap_or_default()))
+		})
+	}
+
+	fn optional_u32<S: AsRef<str>>(&self, names: &[S]) -> Result<Option<u32>, GameDataError> {
+		let Some((column, value)) = self.get(names) else {
+			return Ok(None);
+		};
+		// some sheets use -1 for "none"
+		if value.is_empty() || value.starts_with('-') {
+			return Ok(Some(0));
+		}
+		value
+			.parse()
+			.map(Some)
+			.map_err(|_| GameDataError::InvalidValue {
+				sheet: self.sheet.to_string(),
+				column: column.to_string(),
+				value: value.to_string(),
+			})
+	}
+
+	fn u32<S: AsRef<str>>(&self, names: &[S]) -> Result<u32, GameDataError> {
+		self.optional_u32(names)?.ok_or_else(|| self.missing(names))
+	}
+
+	fn bool<S: AsRef<str>>(&self, names: &[S]) -> Result<bool, GameDataError> {
+		let (column, value) = self.get(names).ok_or_else(|| self.missing(names))?;
+		match value.to_ascii_lowercase().as_str() {
+			"true" | "1" => Ok(true),
+			"false" | "0" | "" => Ok(false),
+			_ => Err(GameDataError::InvalidValue {
+				sheet: self.sheet.to_string(),
+				column: column.to_string(),
+				value: value.to_string(),
+			}),
+		}
+	}
+
+	fn missing<S: AsRef<str>>(&self, names: &[S]) -> GameDataError {
+		GameDataError::MissingColumn {
+			sheet: self.sheet.to_string(),
+			column: names
+				.first()
+				.map(|name| name.as_ref().to_string())
+				.unwrap_or_default(),
+		}
+	}
+}
+
+#[derive(Debug)]
+pub enum GameDataError {
+	Io(PathBuf, std::io::Error),
+	Csv(&'static str, csv::Error),
+	MissingColumn {
+		sheet: String,
+		column: String,
+	},
+	InvalidValue {
+		sheet: String,
+		column: String,
+		value: String,
+	},
+	/// `recipe` refers to a row of `RecipeLevelTable.csv` that doesn't exist.
+	UnknownRecipeLevel {
+		recipe: u32,
+		level: u32,
+	},
+}
+impl fmt::Display for GameDataError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			GameDataError::Io(path, err) => write!(f, "couldn't read {}: {}", path.display(), err),
+			GameDataError::Csv(sheet, err) => write!(f, "invalid {} sheet: {}", sheet, err),
+			GameDataError::MissingColumn { sheet, column } => {
+				write!(f, "the {} sheet has no `{}` column", sheet, column)
+			}
+			GameDataError::InvalidValue {
+				sheet,
+				column,
+				value,
+			} => write!(
+				f,
+				"invalid value `{}` in column `{}` of the {} sheet",
+				value, column, sheet
+			),
+			GameDataError::UnknownRecipeLevel { recipe, level } => write!(
+				f,
+				"recipe {} uses recipe level {}, which isn't in the RecipeLevelTable sheet",
+				recipe, level
+			),
+		}
+	}
+}
+impl std::error::Error for GameDataError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			GameDataError::Io(_, err) => Some(err),
+			GameDataError::Csv(_, err) => Some(err),
+			_ => None,
+		}
+	}
+}
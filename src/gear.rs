@@ -0,0 +1,605 @@
+//! Crafter stats from equipped gear and melded materia.
+//!
+//! Item and materia data is supplied by the user, either built in code or, with the `serde`
+//! feature, loaded from a local JSON file:
+//!
+//! ```json
+//! {
+//!     "items": [
+//!         { "id": "main", "name": "Splendorous Saw", "craftsmanship": 5000, "control": 2400,
+//!           "materia_slots": 2, "overmeld": false, "splendorous": true,
+//!           "caps": { "craftsmanship": 5040, "control": 2440 } }
+//!     ],
+//!     "materia": [
+//!         { "id": "cms12", "stat": "Craftsmanship", "value": 54, "grade": 12 }
+//!     ]
+//! }
+//! ```
+
+use bon::bon;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+
+use crate::types::{structs::CrafterStats, QualityTarget, Simulation};
+
+/// The most materia slots an item can have, overmelding included.
+pub const MAX_MATERIA_SLOTS: usize = 5;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum Stat {
+	Craftsmanship,
+	Control,
+	Cp,
+}
+
+/// An amount of each crafting stat.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Stats {
+	pub craftsmanship: u32,
+	pub control: u32,
+	pub cp: u32,
+}
+impl Stats {
+	pub fn get(&self, stat: Stat) -> u32 {
+		match stat {
+			Stat::Craftsmanship => self.craftsmanship,
+			Stat::Control => self.control,
+			Stat::Cp => self.cp,
+		}
+	}
+
+	fn get_mut(&mut self, stat: Stat) -> &mut u32 {
+		match stat {
+			Stat::Craftsmanship => &mut self.craftsmanship,
+			Stat::Control => &mut self.control,
+			Stat::Cp => &mut self.cp,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct GearItem {
+	pub id: String,
+	pub name: String,
+	pub craftsmanship: u32,
+	pub control: u32,
+	pub cp: u32,
+	/// The number of guaranteed materia slots.
+	pub materia_slots: u32,
+	/// Whether materia can be melded past the guaranteed slots, up to [`MAX_MATERIA_SLOTS`].
+	pub overmeld: bool,
+	pub splendorous: bool,
+	/// The highest each stat of this item can reach through materia. Stats without a cap
+	/// (left at 0) aren't limited.
+	pub caps: Stats,
+}
+impl GearItem {
+	pub fn base_stats(&self) -> Stats {
+		Stats {
+			craftsmanship: self.craftsmanship,
+			control: self.control,
+			cp: self.cp,
+		}
+	}
+
+	/// How many materia can be melded into this item.
+	pub fn total_slots(&self) -> usize {
+		if self.overmeld {
+			MAX_MATERIA_SLOTS
+		} else {
+			self.materia_slots as usize
+		}
+	}
+
+	/// This item's stats with `materia` melded into it, in slot order, capped per stat.
+	pub fn stats_with(&self, materia: &[&Materia]) -> Result<Stats, GearError> {
+		for (index, materia) in materia.iter().enumerate() {
+			match self.slot(index) {
+				None => {
+					return Err(GearError::TooManyMateria {
+						item: self.id.clone(),
+						slots: self.total_slots(),
+					})
+				}
+				Some(slot) if !materia.fits(slot) => {
+					return Err(GearError::MateriaDoesNotFit {
+						item: self.id.clone(),
+						materia: materia.id.clone(),
+						slot: index,
+					})
+				}
+				Some(_) => (),
+			}
+		}
+
+		let mut stats = self.base_stats();
+		let mut melded = Stats::default();
+		for materia in materia {
+			*melded.get_mut(materia.stat) += materia.value;
+		}
+		for stat in [Stat::Craftsmanship, Stat::Control, Stat::Cp] {
+			let cap = self.caps.get(stat);
+			let value = stats.get(stat) + melded.get(stat);
+			// materia never lower a stat that is already above its cap
+			*stats.get_mut(stat) = if cap == 0 {
+				value
+			} else {
+				value.min(cap.max(stats.get(stat)))
+			};
+		}
+		Ok(stats)
+	}
+
+	/// The kind of the materia slot at `index`, or `None` if the item doesn't have it.
+	pub fn slot(&self, index: usize) -> Option<MateriaSlot> {
+		let guaranteed = self.materia_slots as usize;
+		if index < guaranteed {
+			Some(MateriaSlot::Guaranteed)
+		} else if index < self.total_slots() {
+			Some(MateriaSlot::Overmeld(index - guaranteed + 1))
+		} else {
+			None
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MateriaSlot {
+	Guaranteed,
+	/// The `n`th slot past the guaranteed ones, starting at 1. Later overmeld slots are
+	/// harder to meld into.
+	Overmeld(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Materia {
+	pub id: String,
+	pub stat: Stat,
+	pub value: u32,
+	pub grade: u8,
+	/// The last overmeld slot this materia can be melded into, or `None` if it can go into
+	/// any of them. High grade materia can only be overmelded into the first slot, for
+	/// example.
+	#[cfg_attr(feature = "serde", serde(default))]
+	pub max_overmeld: Option<usize>,
+}
+impl Materia {
+	pub fn fits(&self, slot: MateriaSlot) -> bool {
+		match slot {
+			MateriaSlot::Guaranteed => true,
+			MateriaSlot::Overmeld(n) => self.max_overmeld.map_or(true, |max| n <= max),
+		}
+	}
+}
+
+/// The items and materia a gearset can refer to.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct GearData {
+	pub items: Vec<GearItem>,
+	pub materia: Vec<Materia>,
+}
+impl GearData {
+	/// Parses gear data from JSON, as shown in the [module documentation](self).
+	#[cfg(feature = "serde")]
+	pub fn from_json(json: &str) -> Result<Self, GearError> {
+		Ok(serde_json::from_str(json)?)
+	}
+
+	/// Reads gear data from a local JSON file.
+	#[cfg(feature = "serde")]
+	pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, GearError> {
+		Self::from_json(&std::fs::read_to_string(path)?)
+	}
+
+	pub fn item(&self, id: &str) -> Option<&GearItem> {
+		self.items.iter().find(|item| item.id == id)
+	}
+
+	pub fn materia(&self, id: &str) -> Option<&Materia> {
+		self.materia.iter().find(|materia| materia.id == id)
+	}
+}
+
+/// An equipped item and the materia melded into it, in slot order, by id.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct EquippedItem {
+	pub item: String,
+	#[cfg_attr(feature = "serde", serde(default))]
+	pub materia: Vec<String>,
+}
+
+impl EquippedItem {
+	fn resolve<'a>(
+		&self,
+		data: &'a GearData,
+	) -> Result<(&'a GearItem, Vec<&'a Materia>), GearError> {
+		let item = data
+			.item(&self.item)
+			.ok_or_else(|| GearError::UnknownItem(self.item.clone()))?;
+		let materia = self
+			.materia
+			.iter()
+			.map(|id| {
+				data.materia(id)
+					.ok_or_else(|| GearError::UnknownMateria(id.clone()))
+			})
+			.collect::<Result<_, _>>()?;
+		Ok((item, materia))
+	}
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Gearset {
+	pub items: Vec<EquippedItem>,
+}
+impl Gearset {
+	/// The stats each equipped item gives with its materia, capped per item.
+	pub fn item_stats(&self, data: &GearData) -> Result<Vec<Stats>, GearError> {
+		self.items
+			.iter()
+			.map(|equipped| {
+				let (item, materia) = equipped.resolve(data)?;
+				item.stats_with(&materia)
+			})
+			.collect()
+	}
+
+	/// Adds the stats of this gearset to `base`, which holds the crafter's level and the
+	/// stats they have without gear.
+	///
+	/// `splendorous` is set when a Splendorous tool is equipped.
+	pub fn crafter_stats(
+		&self,
+		data: &GearData,
+		base: &CrafterStats,
+	) -> Result<CrafterStats, GearError> {
+		let mut crafter_stats = base.clone();
+		for stats in self.item_stats(data)? {
+			crafter_stats.craftsmanship += stats.craftsmanship;
+			crafter_stats.control += stats.control;
+			crafter_stats.cp += stats.cp;
+		}
+		crafter_stats.splendorous = base.splendorous
+			|| self
+				.items
+				.iter()
+				.filter_map(|equipped| data.item(&equipped.item))
+				.any(|item| item.splendorous);
+		Ok(crafter_stats)
+	}
+
+	/// The kind of slot each materia is melded into, for each equipped item in order.
+	pub fn materia_slots(&self, data: &GearData) -> Result<Vec<Vec<MateriaSlot>>, GearError> {
+		self.items
+			.iter()
+			.map(|equipped| {
+				let item = data
+					.item(&equipped.item)
+					.ok_or_else(|| GearError::UnknownItem(equipped.item.clone()))?;
+				(0..equipped.materia.len())
+					.map(|index| {
+						item.slot(index).ok_or_else(|| GearError::TooManyMateria {
+							item: item.id.clone(),
+							slots: item.total_slots(),
+						})
+					})
+					.collect()
+			})
+			.collect()
+	}
+}
+
+/// A materia the meld optimizer may use, and what each one costs.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct MeldOption {
+	pub materia: String,
+	pub cost: u32,
+}
+
+/// The melds found by [`Gearset::optimize_melds`].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct MeldPlan {
+	/// The gearset with the new materia melded into its open slots.
+	pub gearset: Gearset,
+	pub cost: u32,
+	/// The number of materia added.
+	pub materia_count: usize,
+	pub crafter_stats: CrafterStats,
+}
+
+#[bon]
+impl Gearset {
+	/// Finds the cheapest way to fill this gearset's open materia slots so that the crafter
+	/// reaches at least `target`, or `None` if no melds do. Ties are broken by the number of
+	/// materia, so giving every option the same cost finds the fewest materia.
+	///
+	/// Materia already melded stay where they are. Item caps and overmeld limits are
+	/// respected as in [`Gearset::crafter_stats`].
+	#[builder(finish_fn = run)]
+	pub fn optimize_melds(
+		&self,
+		data: &GearData,
+		base: &CrafterStats,
+		options: &[MeldOption],
+		target: Stats,
+	) -> Result<Option<MeldPlan>, GearError> {
+		let options = options
+			.iter()
+			.map(|option| {
+				data.materia(&option.materia)
+					.map(|materia| (materia, option.cost))
+					.ok_or_else(|| GearError::UnknownMateria(option.materia.clone()))
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+		let needed = Stats {
+			craftsmanship: target.craftsmanship.saturating_sub(base.craftsmanship),
+			control: target.control.saturating_sub(base.control),
+			cp: target.cp.saturating_sub(base.cp),
+		};
+		let capped = |stats: Stats| Stats {
+			craftsmanship: stats.craftsmanship.min(needed.craftsmanship),
+			control: stats.control.min(needed.control),
+			cp: stats.cp.min(needed.cp),
+		};
+		let add = |a: Stats, b: Stats| Stats {
+			craftsmanship: a.craftsmanship + b.craftsmanship,
+			control: a.control + b.control,
+			cp: a.cp + b.cp,
+		};
+
+		// for every item, the stats of every way to fill its open slots
+		let mut item_melds = vec![];
+		for equipped in &self.items {
+			let (item, melded) = equipped.resolve(data)?;
+			let open_slots = item.total_slots().saturating_sub(melded.len());
+			let melds: Vec<_> = meld_combinations(&options, open_slots)
+				.into_iter()
+				.filter_map(|combination| {
+					let mut materia = melded.clone();
+					materia.extend(combination.iter().map(|&ix| options[ix].0));
+					let stats = item.stats_with(&materia).ok()?;
+					let cost = combination.iter().map(|&ix| options[ix].1).sum::<u32>();
+					Some((stats, cost, combination))
+				})
+				.collect();
+			item_melds.push(melds);
+		}
+
+		// the cheapest melds reaching each combination of stats, capped at what is needed,
+		// with a pointer back to the previous item's entry. Ordered so ties always resolve
+		// to the same plan
+		type Entry = ((u32, usize), Option<StatsKey>, usize);
+		let mut layers: Vec<BTreeMap<StatsKey, Entry>> = vec![];
+		let mut previous: BTreeMap<StatsKey, Entry> =
+			BTreeMap::from([(StatsKey::from(Stats::default()), ((0, 0), None, 0))]);
+		for melds in &item_melds {
+			let mut next: BTreeMap<StatsKey, Entry> = BTreeMap::new();
+			for (key, ((cost, count), ..)) in &previous {
+				for (meld_ix, (stats, meld_cost, combination)) in melds.iter().enumerate() {
+					let reached = StatsKey::from(capped(add(key.stats(), *stats)));
+					let score = (cost + meld_cost, count + combination.len());
+					if next
+						.get(&reached)
+						.map_or(true, |(existing, ..)| score < *existing)
+					{
+						next.insert(reached, (score, Some(*key), meld_ix));
+					}
+				}
+			}
+			layers.push(previous);
+			previous = next;
+		}
+
+		let Some(((cost, materia_count), ..)) = previous.get(&StatsKey::from(needed)).copied()
+		else {
+			return Ok(None);
+		};
+		let mut gearset = self.clone();
+		let mut key = Some(StatsKey::from(needed));
+		let mut layer = previous;
+		for (item_ix, melds) in item_melds.iter().enumerate().rev() {
+			let (_, previous_key, meld_ix) = layer[&key.expect("every item has an entry")];
+			gearset.items[item_ix]
+				.materia
+				.extend(melds[meld_ix].2.iter().map(|&ix| options[ix].0.id.clone()));
+			key = previous_key;
+			layer = layers.pop().expect("one layer per item");
+		}
+		let crafter_stats = gearset.crafter_stats(data, base)?;
+
+		Ok(Some(MeldPlan {
+			gearset,
+			cost,
+			materia_count,
+			crafter_stats,
+		}))
+	}
+}
+
+#[bon]
+impl Simulation {
+	/// The lowest craftsmanship, control and CP with which this simulation's rotation still
+	/// succeeds in linear mode and reaches `target`, or `None` if it doesn't with the
+	/// current stats.
+	///
+	/// Each stat is first lowered on its own, keeping the others as they are. If the rotation
+	/// fails with all of them lowered together, as a stat can make up for another, they are
+	/// instead lowered one after the other, keeping the ones already lowered. Either way,
+	/// the rotation reaches `target` with the returned stats.
+	///
+	/// The result can be used as the target of [`Gearset::optimize_melds`].
+	#[builder(finish_fn = run)]
+	pub fn minimum_stats(&self, target: Option<QualityTarget>) -> Option<Stats> {
+		let reaches = |stats: Stats| {
+			let mut simulation = self.clone();
+			simulation.crafter_stats.craftsmanship = stats.craftsmanship;
+			simulation.crafter_stats.control = stats.control;
+			simulation.crafter_stats.cp = stats.cp;
+			simulation.reset();
+			let result = simulation.start().linear(true).run();
+			result.success && target.map_or(true, |target| target.is_reached_by(&result))
+		};
+		// the rotation only gets stronger with higher stats, so binary search the lowest
+		// value of `stat` between `low` and the one in `stats`, which has to work
+		let lowest = |stats: Stats, stat: Stat, mut low: u32| {
+			let mut high = stats.get(stat);
+			while low < high {
+				let middle = low + (high - low) / 2;
+				let mut lowered = stats;
+				*lowered.get_mut(stat) = middle;
+				if reaches(lowered) {
+					high = middle;
+				} else {
+					low = middle + 1;
+				}
+			}
+			high
+		};
+
+		let current = Stats {
+			craftsmanship: self.crafter_stats.craftsmanship,
+			control: self.crafter_stats.control,
+			cp: self.crafter_stats.cp,
+		};
+		if !reaches(current) {
+			return None;
+		}
+
+		let mut minimum = Stats::default();
+		for stat in [Stat::Craftsmanship, Stat::Control, Stat::Cp] {
+			*minimum.get_mut(stat) = lowest(current, stat, 0);
+		}
+		if reaches(minimum) {
+			return Some(minimum);
+		}
+
+		// no stat can go below its own minimum, whatever the others are
+		let mut lowered = current;
+		for stat in [Stat::Craftsmanship, Stat::Control, Stat::Cp] {
+			*lowered.get_mut(stat) = lowest(lowered, stat, minimum.get(stat));
+		}
+		Some(lowered)
+	}
+}
+
+#[derive(Clone, Copy, Eq, Ord, PartialEq, PartialOrd)]
+struct StatsKey(u32, u32, u32);
+impl StatsKey {
+	fn stats(&self) -> Stats {
+		Stats {
+			craftsmanship: self.0,
+			control: self.1,
+			cp: self.2,
+		}
+	}
+}
+impl From<Stats> for StatsKey {
+	fn from(value: Stats) -> Self {
+		Self(value.craftsmanship, value.control, value.cp)
+	}
+}
+
+// every multiset of up to `slots` options, as option indices in slot order. Options with the
+// strictest overmeld limit go first, which is the only order that can fit if any does.
+fn meld_combinations(options: &[(&Materia, u32)], slots: usize) -> Vec<Vec<usize>> {
+	let mut order: Vec<_> = (0..options.len()).collect();
+	order.sort_by_key(|&ix| options[ix].0.max_overmeld.unwrap_or(usize::MAX));
+
+	let mut combinations = vec![vec![]];
+	let mut frontier = vec![(vec![], 0)];
+	for _ in 0..slots {
+		let mut next = vec![];
+		for (combination, start) in frontier {
+			for (position, &option) in order.iter().enumerate().skip(start) {
+				let mut combination: Vec<usize> = combination.clone();
+				combination.push(option);
+				combinations.push(combination.clone());
+				next.push((combination, position));
+			}
+		}
+		frontier = next;
+	}
+	combinations
+}
+
+#[derive(Debug)]
+pub enum GearError {
+	UnknownItem(String),
+	UnknownMateria(String),
+	/// More materia were melded into `item` than it has slots.
+	TooManyMateria {
+		item: String,
+		slots: usize,
+	},
+	/// `materia` can't be melded into slot `slot` (zero-indexed) of `item`.
+	MateriaDoesNotFit {
+		item: String,
+		materia: String,
+		slot: usize,
+	},
+	#[cfg(feature = "serde")]
+	Json(serde_json::Error),
+	#[cfg(feature = "serde")]
+	Io(std::io::Error),
+}
+impl fmt::Display for GearError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			GearError::UnknownItem(id) => write!(f, "unknown item `{}`", id),
+			GearError::UnknownMateria(id) => write!(f, "unknown materia `{}`", id),
+			GearError::TooManyMateria { item, slots } => {
+				write!(f, "item `{}` only has {} materia slots", item, slots)
+			}
+			GearError::MateriaDoesNotFit {
+				item,
+				materia,
+				slot,
+			} => write!(
+				f,
+				"materia `{}` can't be melded into slot {} of item `{}`",
+				materia,
+				slot + 1,
+				item
+			),
+			#[cfg(feature = "serde")]
+			GearError::Json(err) => write!(f, "invalid gear data: {}", err),
+			#[cfg(feature = "serde")]
+			GearError::Io(err) => write!(f, "couldn't read gear data: {}", err),
+		}
+	}
+}
+impl std::error::Error for GearError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			#[cfg(feature = "serde")]
+			GearError::Json(err) => Some(err),
+			#[cfg(feature = "serde")]
+			GearError::Io(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for GearError {
+	fn from(value: serde_json::Error) -> Self {
+		GearError::Json(value)
+	}
+}
+#[cfg(feature = "serde")]
+impl From<std::io::Error> for GearError {
+	fn from(value: std::io::Error) -> Self {
+		GearError::Io(value)
+	}
+}
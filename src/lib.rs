@@ -37,6 +37,13 @@
 //! ```
 
 #![forbid(unsafe_code)]
+pub mod crafting_macro;
+#[cfg(feature = "csv")]
+pub mod game_data;
+pub mod gear;
+pub mod solver;
+#[cfg(feature = "serde")]
+pub mod teamcraft;
 pub mod types;
 
 #[cfg(test)]
@@ -0,0 +1,258 @@
+//! Search for a rotation that completes a recipe with as much quality as possible.
+//!
+//! The solver walks the action space with a beam search, stepping real [`Simulation`]s, so
+//! every rule the simulator models (level requirements, CP and durability costs, combos and
+//! buffs) applies to the search exactly as it does to a replay. Conditions are assumed to
+//! stay Normal, and actions that could fail or that need a Good condition are never used, so
+//! a returned rotation gives the same result whether it's replayed linearly or not.
+
+use bon::builder;
+use std::collections::HashMap;
+
+use crate::types::{
+	actions,
+	enums::{Buff, CraftingActionEnum, GameVersion},
+	structs::{Craft, CrafterStats, Ingredient, SimulationResult},
+	traits::CraftingAction,
+	Simulation,
+};
+
+const DEFAULT_BEAM_WIDTH: usize = 200;
+const DEFAULT_MAX_STEPS: usize = 40;
+
+// progress actions tried, in order, when checking whether a state can still finish the craft
+const FINISHERS: [CraftingActionEnum; 4] = [
+	CraftingActionEnum::Groundwork(actions::Groundwork),
+	CraftingActionEnum::PrudentSynthesis(actions::PrudentSynthesis),
+	CraftingActionEnum::CarefulSynthesis(actions::CarefulSynthesis),
+	CraftingActionEnum::BasicSynthesis(actions::BasicSynthesis),
+];
+const REPAIRS: [CraftingActionEnum; 2] = [
+	CraftingActionEnum::ImmaculateMend(actions::ImmaculateMend),
+	CraftingActionEnum::MastersMend(actions::MastersMend),
+];
+
+/// Searches for the rotation that completes `recipe` with the most quality.
+///
+/// Returns the result of replaying the best rotation found, or `None` if no searched
+/// rotation completes the recipe. Larger `beam_width`s search more of the action space,
+/// at the cost of speed.
+#[builder(finish_fn = solve)]
+pub fn solver(
+	recipe: Craft,
+	crafter_stats: CrafterStats,
+	hq_ingredients: Option<Vec<Ingredient>>,
+	/// How many partial rotations are kept after each step. Defaults to 200.
+	beam_width: Option<usize>,
+	/// The longest rotation considered. Defaults to 40.
+	max_steps: Option<usize>,
+	/// The game version whose rules the rotation is searched under. Defaults to the latest.
+	game_version: Option<GameVersion>,
+) -> Option<SimulationResult> {
+	let beam_width = beam_width.unwrap_or(DEFAULT_BEAM_WIDTH).max(1);
+	let max_steps = max_steps.unwrap_or(DEFAULT_MAX_STEPS);
+
+	let initial = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(crafter_stats.clone())
+		.maybe_hq_ingredients(hq_ingredients.clone())
+		.maybe_game_version(game_version)
+		.build();
+	let max_quality = recipe.quality;
+
+	let mut best: Option<Simulation> = None;
+	let mut consider = |simulation: Simulation| {
+		if simulation.success != Some(true) || !meets_required_quality(&simulation) {
+			return;
+		}
+		let is_better = best.as_ref().map_or(true, |current| {
+			let quality = simulation.quality.min(max_quality);
+			let current_quality = current.quality.min(max_quality);
+			quality > current_quality
+				|| (quality == current_quality && simulation.steps.len() < current.steps.len())
+		});
+		if is_better {
+			best = Some(simulation);
+		}
+	};
+
+	let mut beam = vec![initial];
+	for index in 0..max_steps {
+		let mut expanded: HashMap<SearchKey, (f64, Simulation)> = HashMap::new();
+		for simulation in &beam {
+			for action in CraftingActionEnum::ALL {
+				let Some(next) = try_step(simulation, &action, index) else {
+					continue;
+				};
+				if next.success.is_some() {
+					consider(next);
+					continue;
+				}
+				// only keep states that can still complete the craft
+				let Some(finished) = finish(&next) else {
+					continue;
+				};
+				consider(finished);
+
+				let score = score(&next);
+				let key = SearchKey::new(&next);
+				if expanded
+					.get(&key)
+					.map_or(true, |(existing, _)| score > *existing)
+				{
+					expanded.insert(key, (score, next));
+				}
+			}
+		}
+
+		let mut candidates: Vec<_> = expanded.into_values().collect();
+		candidates.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+		candidates.truncate(beam_width);
+		beam = candidates
+			.into_iter()
+			.map(|(_, simulation)| simulation)
+			.collect();
+		if beam.is_empty() {
+			break;
+		}
+	}
+
+	let rotation: Vec<_> = best?.steps.iter().map(|step| step.action).collect();
+	let result = Simulation::builder()
+		.recipe(recipe)
+		.crafter_stats(crafter_stats)
+		.maybe_hq_ingredients(hq_ingredients)
+		.actions(rotation)
+		.maybe_game_version(game_version)
+		.build()
+		.start()
+		.linear(true)
+		.run();
+	result.success.then_some(result)
+}
+
+// applies `action` as step `index` of a rotation in Normal condition, if it is guaranteed
+// to succeed there
+fn try_step(
+	simulation: &Simulation,
+	action: &CraftingActionEnum,
+	index: usize,
+) -> Option<Simulation> {
+	if !action.can_be_used_with_flags(simulation, Some(false), None)
+		|| action.get_success_rate(simulation) < 100
+		|| action.get_cp_cost(simulation) > simulation.available_cp
+		|| *action == actions::FinalAppraisal.into()
+		|| *action == actions::RemoveFinalAppraisal.into()
+	{
+		return None;
+	}
+	let mut next = simulation.clone();
+	next.set_step_state(index, false);
+	let result = next.run_step(action, index, true, false, usize::MAX, Some(0));
+	if result.skipped || result.success != Some(true) || next.success == Some(false) {
+		None
+	} else {
+		Some(next)
+	}
+}
+
+// greedily completes the craft with progress actions and repairs, without touching quality
+fn finish(simulation: &Simulation) -> Option<Simulation> {
+	let mut current = simulation.clone();
+	while current.success.is_none() {
+		let index = current.steps.len();
+		let progress = FINISHERS
+			.iter()
+			.filter_map(|action| try_step(&current, action, index))
+			.max_by_key(|next| (next.success == Some(true), next.progression));
+		current = match progress {
+			Some(next) => next,
+			None => REPAIRS
+				.iter()
+				.find_map(|action| try_step(&current, action, index))
+				// a repair that doesn't allow a progress action afterwards won't help
+				.filter(|next| next.durability > current.durability)?,
+		};
+	}
+	(current.success == Some(true)).then_some(current)
+}
+
+fn meets_required_quality(simulation: &Simulation) -> bool {
+	simulation
+		.recipe
+		.required_quality
+		.map_or(true, |required_quality| {
+			simulation.quality > required_quality
+		})
+}
+
+// how promising a partial rotation is: its quality, plus a rough estimate of the quality
+// its remaining resources and buffs could still add
+fn score(simulation: &Simulation) -> f64 {
+	let max_quality = simulation.recipe.quality as f64;
+	let quality = (simulation.quality as f64).min(max_quality);
+	if quality >= max_quality {
+		// nothing more to gain, so prefer whatever leaves the most room to finish
+		return max_quality * 2.0 + simulation.available_cp as f64 + simulation.durability as f64;
+	}
+
+	let touch: CraftingActionEnum = actions::BasicTouch.into();
+	let base_quality = touch.get_base_quality(simulation) as f64;
+	let inner_quiet = simulation
+		.get_buff(Buff::InnerQuiet)
+		.map_or(0, |b| b.stacks) as f64;
+	let mut multiplier = 1.0 + inner_quiet / 10.0;
+	if simulation.has_buff(Buff::Innovation) {
+		multiplier += 0.25;
+	}
+	if simulation.has_buff(Buff::GreatStrides) {
+		multiplier += 0.25;
+	}
+
+	// roughly one 100 potency touch per 18 CP, limited by durability at 10 per touch
+	let repairs = simulation
+		.get_buff(Buff::Manipulation)
+		.map_or(0, |b| b.duration * 5) as f64;
+	let touches = (simulation.available_cp as f64 / 18.0)
+		.min((simulation.durability as f64 + repairs) / 10.0)
+		.max(0.0);
+	let potential = base_quality * multiplier * touches * 0.5;
+
+	(quality + potential).min(max_quality * 1.5) + simulation.progression as f64 * 1e-6
+}
+
+// the parts of a search state that decide which rotations can follow it
+#[derive(Eq, Hash, PartialEq)]
+struct SearchKey {
+	progression: u32,
+	quality: u32,
+	durability: i32,
+	available_cp: u32,
+	buffs: Vec<(Buff, i32, u32)>,
+	last_action: Option<&'static str>,
+	used_once_per_craft: (bool, bool),
+}
+impl SearchKey {
+	fn new(simulation: &Simulation) -> Self {
+		let mut buffs: Vec<_> = simulation
+			.buffs()
+			.iter()
+			.map(|b| (b.buff, b.duration, b.stacks))
+			.collect();
+		buffs.sort_by_key(|&(buff, ..)| buff as u8);
+		let used = |action: CraftingActionEnum| simulation.steps.iter().any(|s| s.action == action);
+
+		Self {
+			progression: simulation.progression,
+			quality: simulation.quality,
+			durability: simulation.durability,
+			available_cp: simulation.available_cp,
+			buffs,
+			last_action: simulation.steps.last().map(|step| step.action.name()),
+			used_once_per_craft: (
+				used(actions::HeartAndSoul.into()),
+				used(actions::TrainedPerfection.into()),
+			),
+		}
+	}
+}
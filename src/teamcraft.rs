@@ -0,0 +1,89 @@
+//! Import and export of rotations in the JSON format used by ffxiv-teamcraft.
+//!
+//! Teamcraft stores a rotation as an array of action class names, e.g.
+//! `["MuscleMemory", "Manipulation", "BasicTouch", "ByregotsBlessing"]`.
+
+use std::fmt;
+
+use crate::types::enums::CraftingActionEnum;
+
+// actions that Teamcraft can still export from old rotations, but that no longer exist in game
+const RETIRED_ACTIONS: [&str; 12] = [
+	"InnerQuiet",
+	"PatientTouch",
+	"Rumination",
+	"BrandOfTheElements",
+	"NameOfTheElements",
+	"MakersMark",
+	"PieceByPiece",
+	"FlawlessSynthesis",
+	"HastyTouchII",
+	"RapidSynthesisII",
+	"ByregotsBrow",
+	"InnovativeTouch",
+];
+
+#[derive(Debug)]
+pub enum TeamcraftError {
+	/// The input isn't a JSON array of strings.
+	Json(serde_json::Error),
+	/// The action at `index` isn't known to Teamcraft or byregox.
+	UnknownAction { index: usize, name: String },
+	/// The action at `index` was removed from the game and can't be simulated.
+	RetiredAction { index: usize, name: String },
+}
+impl fmt::Display for TeamcraftError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			TeamcraftError::Json(err) => write!(f, "invalid Teamcraft rotation: {}", err),
+			TeamcraftError::UnknownAction { index, name } => {
+				write!(f, "unknown action `{}` at step {}", name, index)
+			}
+			TeamcraftError::RetiredAction { index, name } => write!(
+				f,
+				"action `{}` at step {} no longer exists in game",
+				name, index
+			),
+		}
+	}
+}
+impl std::error::Error for TeamcraftError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			TeamcraftError::Json(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+impl From<serde_json::Error> for TeamcraftError {
+	fn from(value: serde_json::Error) -> Self {
+		TeamcraftError::Json(value)
+	}
+}
+
+/// Parses a rotation exported from Teamcraft.
+pub fn parse_rotation(json: &str) -> Result<Vec<CraftingActionEnum>, TeamcraftError> {
+	let names: Vec<String> = serde_json::from_str(json)?;
+	names
+		.into_iter()
+		.enumerate()
+		.map(|(index, name)| action_from_name(index, name))
+		.collect()
+}
+
+/// Writes a rotation in the format Teamcraft imports.
+pub fn write_rotation(actions: &[CraftingActionEnum]) -> String {
+	let names: Vec<_> = actions.iter().map(|action| action.name()).collect();
+	serde_json::to_string(&names).expect("a list of strings is always valid JSON")
+}
+
+fn action_from_name(index: usize, name: String) -> Result<CraftingActionEnum, TeamcraftError> {
+	// byregox action names mirror Teamcraft's class names
+	if let Some(action) = CraftingActionEnum::from_name(&name) {
+		Ok(action)
+	} else if RETIRED_ACTIONS.contains(&name.as_str()) {
+		Err(TeamcraftError::RetiredAction { index, name })
+	} else {
+		Err(TeamcraftError::UnknownAction { index, name })
+	}
+}
@@ -1,13 +1,24 @@
 use anyhow::Result;
 use std::collections::{HashMap, HashSet};
 
+#[cfg(feature = "csv")]
+use crate::game_data::{GameDataError, RecipeDatabase};
+use crate::gear::{
+	EquippedItem, GearData, GearError, GearItem, Gearset, Materia, MateriaSlot, MeldOption, Stat,
+	Stats,
+};
 use crate::types::{
 	actions,
-	enums::{Buff, CraftingActionEnum, StepState},
-	structs::{Craft, CrafterLevels, CrafterStats, CraftingLevel},
+	enums::{Buff, CrafterTrait, CraftingActionEnum, FailCause, GameVersion, StepState},
+	structs::{
+		CollectableResult, Craft, CrafterLevels, CrafterStats, CraftingLevel, Ingredient,
+		SimulationResult,
+	},
 	tables,
-	traits::CraftingAction,
-	Simulation,
+	traits::{CraftingAction, GeneralAction},
+	Comparison, ConditionRates, ConditionSet, Consumable, ConsumableBonuses, HqIngredientOption,
+	LintKind, LintWarning, QualityTarget, RecipeConditionRates, RotationDifference, Script,
+	ScriptCondition, ScriptParseError, ScriptStep, SelectedConsumable, Simulation, StatBonus,
 };
 
 #[test]
@@ -577,8 +588,8 @@ fn test_conditions_for_normal_recipe() -> Result<()> {
 		.build();
 
 	assert_eq!(
-		sim.possible_conditions(),
-		&HashSet::from([
+		sim.possible_conditions().iter().collect::<HashSet<_>>(),
+		HashSet::from([
 			StepState::Normal,
 			StepState::Good,
 			StepState::Excellent,
@@ -636,8 +647,8 @@ fn test_expert_one_conditions() -> Result<()> {
 		.build();
 
 	assert_eq!(
-		sim.possible_conditions(),
-		&HashSet::from([
+		sim.possible_conditions().iter().collect::<HashSet<_>>(),
+		HashSet::from([
 			StepState::Normal,
 			StepState::Good,
 			StepState::Centered,
@@ -664,8 +675,8 @@ fn test_expert_two_conditions() -> Result<()> {
 		.build();
 
 	assert_eq!(
-		sim.possible_conditions(),
-		&HashSet::from([
+		sim.possible_conditions().iter().collect::<HashSet<_>>(),
+		HashSet::from([
 			StepState::Normal,
 			StepState::Good,
 			StepState::Sturdy,
@@ -692,10 +703,11 @@ fn test_expert_two_condition_rates() -> Result<()> {
 	let mut sim = Simulation::builder()
 		.recipe(recipe)
 		.crafter_stats(stats)
+		.seed(0)
 		.build();
 
 	let mut condition_rates: HashMap<_, _> =
-		HashMap::from_iter(sim.possible_conditions().iter().map(|&cond| (cond, 0)));
+		HashMap::from_iter(sim.possible_conditions().iter().map(|cond| (cond, 0)));
 	let num_samples = 100_000;
 	for _ in 0..num_samples {
 		sim.tick_state();
@@ -1233,6 +1245,2483 @@ fn test_groundwork_efficiency_with_trained_perfection() -> Result<()> {
 	Ok(())
 }
 
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_action_names() -> Result<()> {
+	let actions: Vec<CraftingActionEnum> = vec![
+		actions::BasicTouch.into(),
+		actions::ByregotsBlessing.into(),
+		actions::WasteNotII.into(),
+	];
+	let json = serde_json::to_string(&actions)?;
+	assert_eq!(json, r#"["BasicTouch","ByregotsBlessing","WasteNotII"]"#);
+	assert_eq!(
+		serde_json::from_str::<Vec<CraftingActionEnum>>(&json)?,
+		actions
+	);
+	assert!(serde_json::from_str::<CraftingActionEnum>(r#""MakersMark""#).is_err());
+
+	assert_eq!(
+		serde_json::to_string(&StepState::GoodOmen)?,
+		r#""GoodOmen""#
+	);
+	assert_eq!(
+		serde_json::to_string(&CraftingLevel::unchecked_new(90))?,
+		"90"
+	);
+	assert!(serde_json::from_str::<CraftingLevel>("101").is_err());
+
+	Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_result_round_trip() -> Result<()> {
+	let recipe = generate_recipe_rlvl(3864, 80, 517, 80, 2000, 5200, 121, 105);
+	let stats = generate_stats(90, 2763, 2780, 545);
+	let sim = Simulation::builder()
+		.recipe(recipe)
+		.actions(vec![
+			actions::Manipulation.into(),
+			actions::Reflect.into(),
+			actions::BasicTouch.into(),
+			actions::BasicSynthesis.into(),
+		])
+		.crafter_stats(stats)
+		.build();
+
+	let result = sim.start().linear(true).run();
+	let json = serde_json::to_string(&result)?;
+	let mut reloaded: crate::types::structs::SimulationResult = serde_json::from_str(&json)?;
+	assert_eq!(
+		serde_json::to_string(&reloaded.steps)?,
+		serde_json::to_string(&result.steps)?
+	);
+	assert_eq!(reloaded.hq_percent, result.hq_percent);
+	assert_eq!(reloaded.simulation.quality, result.simulation.quality);
+	assert_eq!(
+		reloaded.simulation.progression,
+		result.simulation.progression
+	);
+	assert_eq!(
+		reloaded.simulation.possible_conditions(),
+		result.simulation.possible_conditions()
+	);
+
+	// buff callbacks are restored, so a reloaded simulation keeps repairing
+	reloaded.simulation.durability -= 20;
+	let durability = reloaded.simulation.durability;
+	let manipulation = reloaded
+		.simulation
+		.get_buff(Buff::Manipulation)
+		.cloned()
+		.unwrap();
+	manipulation.tick(&mut reloaded.simulation, &actions::Observe.into());
+	assert_eq!(reloaded.simulation.durability, durability + 5);
+
+	Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_buff_callbacks() -> Result<()> {
+	let recipe = generate_recipe_rlvl(3864, 80, 517, 80, 1200, 5200, 121, 105);
+	let stats = CrafterStats {
+		specialist: true,
+		..generate_stats(100, 4000, 4000, 600)
+	};
+	let mut checked = HashSet::new();
+	for action in CraftingActionEnum::ALL {
+		let mut sim = Simulation::builder()
+			.recipe(recipe.clone())
+			.crafter_stats(stats.clone())
+			.build();
+		if !action.can_be_used(&sim) {
+			continue;
+		}
+		action.execute(&mut sim);
+		for buff in sim.buffs() {
+			let json = serde_json::to_string(buff)?;
+			let reloaded: crate::types::structs::EffectiveBuff = serde_json::from_str(&json)?;
+			assert_eq!(
+				reloaded.tick.is_some(),
+				buff.tick.is_some(),
+				"{:?}",
+				buff.buff
+			);
+			assert_eq!(
+				reloaded.on_expire.is_some(),
+				buff.on_expire.is_some(),
+				"{:?}",
+				buff.buff
+			);
+			checked.insert(buff.buff);
+		}
+	}
+	assert!(checked.contains(&Buff::Manipulation));
+	assert!(checked.contains(&Buff::HeartAndSoul));
+
+	Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_teamcraft_rotation_round_trip() -> Result<()> {
+	let json = r#"["MuscleMemory","Manipulation","Veneration","WasteNotII","Groundwork","BasicTouch","StandardTouch","AdvancedTouch","ByregotsBlessing","CarefulSynthesis"]"#;
+	let rotation = crate::teamcraft::parse_rotation(json)?;
+	assert_eq!(rotation.len(), 10);
+	assert_eq!(rotation[0], actions::MuscleMemory.into());
+	assert_eq!(rotation[8], actions::ByregotsBlessing.into());
+	assert_eq!(crate::teamcraft::write_rotation(&rotation), json);
+
+	Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_teamcraft_rotation_errors() -> Result<()> {
+	use crate::teamcraft::{parse_rotation, TeamcraftError};
+
+	assert!(matches!(
+		parse_rotation(r#"["Reflect","InnerQuiet"]"#),
+		Err(TeamcraftError::RetiredAction { index: 1, .. })
+	));
+	// retired once, but simulated again for Endwalker
+	assert_eq!(
+		parse_rotation(r#"["Observe","FocusedTouch"]"#)?,
+		vec![actions::Observe.into(), actions::FocusedTouch.into()]
+	);
+	assert!(matches!(
+		parse_rotation(r#"["Reflect","BasicTuoch"]"#),
+		Err(TeamcraftError::UnknownAction { index: 1, .. })
+	));
+	assert!(matches!(
+		parse_rotation(r#"{"rotation":[]}"#),
+		Err(TeamcraftError::Json(_))
+	));
+
+	Ok(())
+}
+
+#[test]
+fn test_macro_generation() -> Result<()> {
+	use crate::crafting_macro::{self, MacroNotification};
+
+	let rotation: Vec<CraftingActionEnum> = vec![actions::Reflect.into(); 20]
+		.into_iter()
+		.chain([actions::Innovation.into(), actions::ByregotsBlessing.into()])
+		.collect();
+	let macros = crafting_macro::generate().actions(&rotation).call();
+	assert_eq!(macros.len(), 2);
+	assert_eq!(macros[0].lines().count(), 15);
+	assert_eq!(macros[0].lines().next(), Some("/ac \"Reflect\" <wait.3>"));
+	assert_eq!(
+		macros[1].lines().collect::<Vec<_>>()[5..],
+		[
+			"/ac \"Innovation\" <wait.2>",
+			"/ac \"Byregot's Blessing\" <wait.3>"
+		]
+	);
+
+	let macros = crafting_macro::generate()
+		.actions(&rotation)
+		.notification(MacroNotification::SoundEffect(1))
+		.call();
+	assert_eq!(macros.len(), 2);
+	assert_eq!(macros[0].lines().count(), 15);
+	assert_eq!(
+		macros[0].lines().last(),
+		Some("/echo Macro #1 finished <se.1>")
+	);
+	assert_eq!(
+		macros[1].lines().last(),
+		Some("/echo Macro #2 finished <se.1>")
+	);
+	assert_eq!(crafting_macro::parse(&macros.join("\n"))?, rotation);
+
+	Ok(())
+}
+
+#[test]
+fn test_macro_parsing() -> Result<()> {
+	use crate::crafting_macro::{parse, MacroParseError};
+
+	let text = "/mlock
+/ac Muscle Memory <wait.3>
+/ac \"Waste Not II\" <wait.2>
+/action Byregot’s Blessing <wait.3>
+/ac \"Byregots Blessing\"
+/statusoff \"Final Appraisal\" <wait.3>
+/echo done <se.2>";
+	assert_eq!(
+		parse(text)?,
+		vec![
+			actions::MuscleMemory.into(),
+			actions::WasteNotII.into(),
+			actions::ByregotsBlessing.into(),
+			actions::ByregotsBlessing.into(),
+			actions::RemoveFinalAppraisal.into(),
+		] as Vec<CraftingActionEnum>
+	);
+	assert_eq!(
+		parse("/ac Observe\n/ac \"Brand of Wind\" <wait.3>"),
+		Err(MacroParseError::UnknownAction {
+			line: 1,
+			name: "Brand of Wind".to_string()
+		})
+	);
+
+	Ok(())
+}
+
+#[test]
+fn test_seeded_simulation_is_reproducible() -> Result<()> {
+	// generateRecipe(480, 6178, 36208, 110, 90, 483)
+	let recipe = Craft {
+		expert: Some(true),
+		conditions_flag: 483,
+		..generate_recipe_rlvl(3864, 80, 480, 80, 6178, 36208, 110, 90)
+	};
+	let stats = generate_stats(90, 2745, 2885, 626);
+	let rotation: Vec<CraftingActionEnum> = vec![
+		actions::Reflect.into(),
+		actions::HastyTouch.into(),
+		actions::HastyTouch.into(),
+		actions::RapidSynthesis.into(),
+		actions::HastyTouch.into(),
+		actions::RapidSynthesis.into(),
+		actions::Observe.into(),
+		actions::RapidSynthesis.into(),
+	];
+	let run = |seed: u64| {
+		Simulation::builder()
+			.recipe(recipe.clone())
+			.crafter_stats(stats.clone())
+			.actions(rotation.clone())
+			.seed(seed)
+			.build()
+			.start()
+			.run()
+	};
+
+	let first = run(7);
+	let second = run(7);
+	assert_eq!(first.simulation.quality, second.simulation.quality);
+	assert_eq!(first.simulation.progression, second.simulation.progression);
+	for (a, b) in first.steps.iter().zip(second.steps.iter()) {
+		assert_eq!(a.state, b.state);
+		assert_eq!(a.success, b.success);
+	}
+
+	// with this many rolls, some seed must produce a different run
+	assert!((0..20).map(run).any(|result| {
+		result
+			.steps
+			.iter()
+			.zip(first.steps.iter())
+			.any(|(a, b)| a.state != b.state || a.success != b.success)
+	}));
+
+	Ok(())
+}
+
+#[test]
+fn test_injected_rng() -> Result<()> {
+	use rand::{rngs::StdRng, SeedableRng};
+
+	let recipe = generate_recipe_rlvl(3864, 80, 480, 80, 6178, 36208, 110, 90);
+	let stats = generate_stats(90, 2745, 2885, 626);
+	let mut seeded = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(stats.clone())
+		.seed(11)
+		.build();
+	let mut injected = Simulation::builder()
+		.recipe(recipe)
+		.crafter_stats(stats)
+		.rng(Box::new(StdRng::seed_from_u64(11)))
+		.build();
+	for _ in 0..100 {
+		seeded.tick_state();
+		injected.tick_state();
+		assert_eq!(seeded.state(), injected.state());
+	}
+
+	Ok(())
+}
+
+#[test]
+fn test_batch_statistics() -> Result<()> {
+	// generateRecipe(480, 6178, 36208, 110, 90, 483)
+	let recipe = Craft {
+		expert: Some(true),
+		conditions_flag: 483,
+		..generate_recipe_rlvl(3864, 80, 480, 80, 1500, 36208, 110, 90)
+	};
+	let stats = generate_stats(90, 2745, 2885, 626);
+	let sim = Simulation::builder()
+		.recipe(recipe)
+		.crafter_stats(stats)
+		.actions(vec![
+			actions::Reflect.into(),
+			actions::HastyTouch.into(),
+			actions::HastyTouch.into(),
+			actions::RapidSynthesis.into(),
+			actions::RapidSynthesis.into(),
+			actions::RapidSynthesis.into(),
+			actions::RapidSynthesis.into(),
+		])
+		.build();
+
+	let single_threaded = sim.batch().runs(500).seed(3).threads(1).run();
+	let multi_threaded = sim.batch().runs(500).seed(3).threads(4).run();
+	assert_eq!(single_threaded.runs, 500);
+	assert_eq!(single_threaded.successes, multi_threaded.successes);
+	assert_eq!(
+		single_threaded.quality.samples(),
+		multi_threaded.quality.samples()
+	);
+	assert_eq!(single_threaded.fail_causes, multi_threaded.fail_causes);
+
+	let result = single_threaded;
+	assert!(result.success_rate() > 0.0 && result.success_rate() < 1.0);
+	assert_eq!(result.hq_percent_distribution.iter().sum::<usize>(), 500);
+	assert_eq!(
+		result.successes + result.unfinished + result.fail_causes.values().sum::<usize>(),
+		500
+	);
+	assert!(result.quality.min() <= result.quality.median());
+	assert!(result.quality.median() <= result.quality.max());
+	assert!(result.average_final_step > 3.0 && result.average_final_step <= 7.0);
+
+	Ok(())
+}
+
+#[test]
+fn test_random_conditions() -> Result<()> {
+	// generateRecipe(480, 6178, 36208, 110, 90, 483)
+	let recipe = Craft {
+		expert: Some(true),
+		conditions_flag: 483,
+		..generate_recipe_rlvl(3864, 80, 480, 80, 6178, 36208, 110, 90)
+	};
+	let stats = generate_stats(90, 2745, 2885, 626);
+	let build = || {
+		Simulation::builder()
+			.recipe(recipe.clone())
+			.crafter_stats(stats.clone())
+			.actions(vec![actions::Observe.into(); 30])
+			.step_states(vec![StepState::Pliant])
+			.seed(5)
+			.build()
+	};
+
+	let fixed = build().start().run();
+	assert_eq!(fixed.steps[0].state, StepState::Pliant);
+	assert!(fixed.steps[1..]
+		.iter()
+		.all(|step| step.state == StepState::Normal));
+
+	let random = build().start().random_conditions(true).run();
+	assert_eq!(random.steps[0].state, StepState::Pliant);
+	assert!(random.steps[1..]
+		.iter()
+		.any(|step| step.state != StepState::Normal));
+
+	Ok(())
+}
+
+#[test]
+fn test_exact_outcomes_success_branches() -> Result<()> {
+	let recipe = Craft {
+		conditions_flag: 1, // Normal only
+		..generate_recipe_rlvl(3864, 80, 517, 80, 2000, 5200, 121, 105)
+	};
+	let stats = generate_stats(90, 2763, 2780, 545);
+	let rotation: Vec<CraftingActionEnum> =
+		vec![actions::HastyTouch.into(), actions::HastyTouch.into()];
+	let quality_with_fails = |fails: Vec<usize>| {
+		Simulation::builder()
+			.recipe(recipe.clone())
+			.crafter_stats(stats.clone())
+			.actions(rotation.clone())
+			.fails(fails)
+			.build()
+			.start()
+			.linear(true)
+			.run()
+			.simulation
+			.quality
+	};
+
+	let distribution = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(stats.clone())
+		.actions(rotation.clone())
+		.build()
+		.exact_outcomes();
+	// Hasty Touch has a 60% success rate, which succeeds on rolls 0 through 60
+	let branches = [
+		(vec![], 0.61 * 0.61),
+		(vec![1], 0.61 * 0.39),
+		(vec![0], 0.39 * 0.61),
+		(vec![0, 1], 0.39 * 0.39),
+	];
+	let mut expected: HashMap<u32, f64> = HashMap::new();
+	for (fails, probability) in branches {
+		*expected.entry(quality_with_fails(fails)).or_default() += probability;
+	}
+	// failing either touch leaves the same quality
+	assert_eq!(expected.len(), 3);
+	assert_eq!(distribution.outcomes.len(), 3);
+	let mut expected_quality = 0.0;
+	for (quality, probability) in expected {
+		expected_quality += quality as f64 * probability;
+		let outcome = distribution
+			.outcomes
+			.iter()
+			.find(|outcome| outcome.quality == quality)
+			.unwrap();
+		assert!((outcome.probability - probability).abs() < 1e-9);
+	}
+	assert!((distribution.expected_quality() - expected_quality).abs() < 1e-6);
+	assert_eq!(distribution.success_probability(), 0.0);
+	assert!((distribution.hq_percent_distribution().iter().sum::<f64>() - 1.0).abs() < 1e-9);
+
+	Ok(())
+}
+
+#[test]
+fn test_exact_outcomes_match_batch() -> Result<()> {
+	let recipe = generate_recipe_rlvl(3864, 80, 517, 80, 1200, 5200, 121, 105);
+	let stats = generate_stats(90, 2763, 2780, 545);
+	let sim = Simulation::builder()
+		.recipe(recipe)
+		.crafter_stats(stats)
+		.actions(vec![
+			actions::Reflect.into(),
+			actions::HastyTouch.into(),
+			actions::BasicTouch.into(),
+			actions::RapidSynthesis.into(),
+			actions::RapidSynthesis.into(),
+		])
+		.build();
+
+	let distribution = sim.exact_outcomes();
+	let total_probability: f64 = distribution
+		.outcomes
+		.iter()
+		.map(|outcome| outcome.probability)
+		.sum();
+	assert!((total_probability - 1.0).abs() < 1e-9);
+
+	let batch = sim.batch().runs(20_000).seed(1).run();
+	assert!((distribution.success_probability() - batch.success_rate()).abs() < 0.02);
+	let quality_error = (distribution.expected_quality() - batch.quality.mean()).abs();
+	assert!(quality_error < distribution.expected_quality() * 0.02);
+
+	Ok(())
+}
+
+#[test]
+fn test_solver_finds_complete_rotation() -> Result<()> {
+	let recipe = generate_recipe_rlvl(3864, 80, 517, 80, 1200, 20000, 121, 105);
+	let stats = generate_stats(90, 2763, 2780, 545);
+	let handmade = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(stats.clone())
+		.actions(vec![
+			actions::Reflect.into(),
+			actions::Manipulation.into(),
+			actions::PreparatoryTouch.into(),
+			actions::WasteNotII.into(),
+			actions::BasicTouch.into(),
+			actions::StandardTouch.into(),
+			actions::AdvancedTouch.into(),
+			actions::PrudentTouch.into(),
+			actions::Innovation.into(),
+			actions::PrudentTouch.into(),
+			actions::PrudentTouch.into(),
+			actions::GreatStrides.into(),
+			actions::ByregotsBlessing.into(),
+			actions::Veneration.into(),
+			actions::Groundwork.into(),
+			actions::Groundwork.into(),
+			actions::CarefulSynthesis.into(),
+		])
+		.build()
+		.start()
+		.linear(true)
+		.run();
+	assert!(handmade.success);
+
+	let solved = crate::solver::solver()
+		.recipe(recipe.clone())
+		.crafter_stats(stats.clone())
+		.beam_width(30)
+		.solve()
+		.expect("the recipe can be completed");
+	assert!(solved.success);
+	assert!(
+		solved.simulation.quality.min(recipe.quality)
+			>= handmade.simulation.quality.min(recipe.quality)
+	);
+
+	// replaying the rotation without linear mode gives the same result
+	let replay = Simulation::builder()
+		.recipe(recipe)
+		.crafter_stats(stats)
+		.actions(
+			solved
+				.simulation
+				.steps
+				.iter()
+				.map(|step| step.action)
+				.collect(),
+		)
+		.seed(0)
+		.build()
+		.start()
+		.run();
+	assert!(replay.success);
+	assert_eq!(replay.simulation.quality, solved.simulation.quality);
+	assert_eq!(replay.simulation.progression, solved.simulation.progression);
+
+	Ok(())
+}
+
+#[test]
+fn test_stepper_matches_start() -> Result<()> {
+	let recipe = generate_recipe_rlvl(3864, 80, 517, 80, 1200, 5200, 121, 105);
+	let stats = generate_stats(90, 2763, 2780, 545);
+	let rotation: Vec<CraftingActionEnum> = vec![
+		actions::Reflect.into(),
+		actions::HastyTouch.into(),
+		actions::BasicTouch.into(),
+		actions::RapidSynthesis.into(),
+		actions::RapidSynthesis.into(),
+		actions::CarefulSynthesis.into(),
+	];
+	let sim = Simulation::builder()
+		.recipe(recipe)
+		.crafter_stats(stats)
+		.actions(rotation.clone())
+		.seed(7)
+		.build();
+
+	let mut stepper = sim.clone().stepper().build();
+	for action in &rotation {
+		stepper.step(*action);
+	}
+	let stepped = stepper.into_result();
+	let started = sim.start().run();
+	assert_eq!(stepped.success, started.success);
+	assert_eq!(stepped.simulation.quality, started.simulation.quality);
+	assert_eq!(
+		stepped.simulation.progression,
+		started.simulation.progression
+	);
+	assert_eq!(stepped.simulation.actions, rotation);
+
+	Ok(())
+}
+
+#[test]
+fn test_stepper_undo() -> Result<()> {
+	let recipe = generate_recipe_rlvl(3864, 80, 517, 80, 1200, 5200, 121, 105);
+	let stats = generate_stats(90, 2763, 2780, 545);
+	let sim = Simulation::builder()
+		.recipe(recipe)
+		.crafter_stats(stats)
+		.build();
+
+	let mut stepper = sim.stepper().linear(true).build();
+	assert!(stepper.undo().is_none());
+	stepper.step(actions::Reflect.into());
+	let quality_after_reflect = stepper.current().quality;
+	let touch = stepper.step(actions::BasicTouch.into());
+	assert!(touch.added_quality > 0);
+
+	let undone = stepper.undo().expect("a step was taken");
+	assert_eq!(undone.action, actions::BasicTouch.into());
+	assert_eq!(stepper.len(), 1);
+	assert_eq!(stepper.current().quality, quality_after_reflect);
+	assert_eq!(stepper.current().available_cp, 545 - 6);
+
+	stepper.step(actions::Innovation.into());
+	stepper.step(actions::BasicTouch.into());
+	stepper.step(actions::BasicSynthesis.into());
+	stepper.rewind_to(1);
+	assert_eq!(stepper.len(), 1);
+	assert_eq!(stepper.current().quality, quality_after_reflect);
+	assert!(!stepper.current().has_buff(Buff::Innovation));
+	stepper.rewind_to(0);
+	assert!(stepper.is_empty());
+	assert_eq!(stepper.current().quality, 0);
+
+	Ok(())
+}
+
+#[test]
+fn test_reclaim() -> Result<()> {
+	let recipe = generate_recipe_rlvl(3864, 80, 517, 80, 1200, 5200, 121, 105);
+	let stats = generate_stats(90, 2763, 2780, 100);
+	let sim = Simulation::builder()
+		.recipe(recipe)
+		.crafter_stats(stats)
+		.actions(vec![
+			actions::Reclaim.into(),
+			actions::Reclaim.into(),
+			actions::BasicSynthesis.into(),
+		])
+		.build();
+
+	let result = sim.start().linear(true).run();
+	assert!(result.simulation.has_buff(Buff::Reclaim));
+	assert_eq!(result.simulation.steps[0].cp_difference, -55);
+	// only once per craft
+	assert!(result.simulation.steps[1].skipped);
+	assert_eq!(result.simulation.available_cp, 45);
+
+	Ok(())
+}
+
+#[test]
+fn test_last_possible_reclaim_step() -> Result<()> {
+	let recipe = generate_recipe_rlvl(3864, 80, 517, 80, 1200, 5200, 121, 105);
+	let rotation: Vec<CraftingActionEnum> = vec![
+		actions::BasicTouch.into(),
+		actions::BasicTouch.into(),
+		actions::BasicTouch.into(),
+		actions::BasicTouch.into(),
+	];
+	let sim = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(generate_stats(90, 2763, 2780, 100))
+		.actions(rotation.clone())
+		.build();
+
+	let result = sim.start().linear(true).run();
+	assert!(!result.success);
+	// 46 CP are left before the fourth touch, not enough for Reclaim's 55
+	assert_eq!(result.last_possible_reclaim_step, Some(2));
+	assert_eq!(result.simulation.last_possible_reclaim_step(), Some(2));
+
+	// plenty of CP, but durability runs out at the third touch
+	let sim = Simulation::builder()
+		.recipe(Craft {
+			durability: 30,
+			..recipe.clone()
+		})
+		.crafter_stats(generate_stats(90, 2763, 2780, 500))
+		.actions(rotation.clone())
+		.build();
+	let result = sim.start().linear(true).run();
+	assert_eq!(result.fail_cause, Some(FailCause::DurabilityReachedZero));
+	assert!(result.steps[3].skipped);
+	assert_eq!(result.last_possible_reclaim_step, Some(2));
+
+	let sim = Simulation::builder()
+		.recipe(recipe)
+		.crafter_stats(generate_stats(49, 2763, 2780, 100))
+		.actions(rotation)
+		.build();
+	let result = sim.start().linear(true).run();
+	assert_eq!(result.last_possible_reclaim_step, None);
+
+	Ok(())
+}
+
+#[test]
+fn test_collectable_tiers() -> Result<()> {
+	let recipe = Craft {
+		collectable_thresholds: Some(vec![50, 150, 300]),
+		..generate_recipe_rlvl(3864, 80, 517, 80, 1200, 5200, 121, 105)
+	};
+	let stats = generate_stats(90, 2763, 2780, 545);
+	let mut rotation: Vec<CraftingActionEnum> =
+		vec![actions::BasicTouch.into(), actions::BasicTouch.into()];
+	let sim = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(stats.clone())
+		.actions(rotation.clone())
+		.build();
+
+	// the craft fails without progress, so it yields no collectable
+	let result = sim.start().linear(true).run();
+	assert!(!result.success);
+	assert!(result.collectable.is_none());
+
+	rotation.extend([CraftingActionEnum::from(actions::BasicSynthesis); 5]);
+	let sim = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(stats.clone())
+		.actions(rotation)
+		.build();
+	let result = sim.start().linear(true).run();
+	assert!(result.success);
+	let quality = result.simulation.quality;
+	let collectable = result.collectable.expect("the recipe is collectable");
+	assert_eq!(collectable.collectability, quality / 10);
+	assert_eq!(collectable.tier, Some(0));
+	assert_eq!(collectable.missing_quality, Some(1500 - quality));
+
+	let result = Simulation::builder()
+		.recipe(Craft {
+			collectable_thresholds: None,
+			..recipe
+		})
+		.crafter_stats(stats)
+		.build()
+		.start()
+		.run();
+	assert!(result.collectable.is_none());
+
+	Ok(())
+}
+
+#[test]
+fn test_collectable_result_bounds() -> Result<()> {
+	let below = CollectableResult::new(499, &[50, 150]);
+	assert_eq!(below.tier, None);
+	assert_eq!(below.missing_quality, Some(1));
+
+	let top = CollectableResult::new(1500, &[50, 150]);
+	assert_eq!(top.collectability, 150);
+	assert_eq!(top.tier, Some(1));
+	assert_eq!(top.missing_quality, None);
+
+	Ok(())
+}
+
+#[test]
+fn test_quick_synthesis() -> Result<()> {
+	let recipe = Craft {
+		ingredients: vec![Ingredient {
+			id: "5057".to_string(),
+			amount: 3,
+			..Default::default()
+		}],
+		..generate_recipe_lvl(3864, 16, 80, 31, 866, 50, 30)
+	};
+	let sim = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(generate_stats(80, 2278, 2348, 532))
+		.build();
+
+	let result = sim
+		.quick_synthesis()
+		.attempts(10)
+		.run()
+		.expect("quick synth is allowed");
+	assert!((result.success_rate - 1.0).abs() < 1e-9);
+	assert!((result.expected_successes - 10.0).abs() < 1e-9);
+	assert_eq!(result.expected_material_loss["5057"], 0.0);
+
+	// too much progress for Basic Synthesis to finish before durability runs out
+	let sim = Simulation::builder()
+		.recipe(Craft {
+			progress: 100_000,
+			..recipe.clone()
+		})
+		.crafter_stats(generate_stats(80, 2278, 2348, 532))
+		.build();
+	let result = sim
+		.quick_synthesis()
+		.attempts(10)
+		.run()
+		.expect("quick synth is allowed");
+	assert_eq!(result.success_rate, 0.0);
+	assert_eq!(result.expected_hq, 0.0);
+	assert!((result.expected_material_loss["5057"] - 30.0).abs() < 1e-9);
+
+	let sim = Simulation::builder()
+		.recipe(Craft {
+			quick_synth: Some(false),
+			..recipe
+		})
+		.crafter_stats(generate_stats(80, 2278, 2348, 532))
+		.build();
+	assert!(sim.quick_synthesis().attempts(10).run().is_none());
+
+	Ok(())
+}
+
+#[test]
+fn test_hq_ingredient_optimizer() -> Result<()> {
+	let ingredient = |id: &str, amount, quality| Ingredient {
+		id: id.to_string(),
+		amount,
+		quality,
+		..Default::default()
+	};
+	let recipe = Craft {
+		ingredients: vec![
+			ingredient("a", 2, Some(100)),
+			ingredient("b", 1, Some(250)),
+			ingredient("crystal", 8, None),
+		],
+		..generate_recipe_rlvl(3864, 80, 517, 80, 1200, 5200, 121, 105)
+	};
+	let options = vec![
+		HqIngredientOption {
+			id: "a".to_string(),
+			cost: 10,
+			available: None,
+		},
+		HqIngredientOption {
+			id: "b".to_string(),
+			cost: 30,
+			available: None,
+		},
+	];
+	let finish: Vec<CraftingActionEnum> = vec![actions::BasicSynthesis.into(); 5];
+	let sim = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(generate_stats(90, 2763, 2780, 545))
+		.actions(finish.clone())
+		.build();
+
+	let plan = sim
+		.optimize_hq_ingredients()
+		.options(options.clone())
+		.target(QualityTarget::Quality(200))
+		.run()
+		.expect("the target is reachable");
+	assert_eq!(plan.cost, 20);
+	assert_eq!(plan.starting_quality, 200);
+	assert_eq!(plan.hq_ingredients.len(), 1);
+	assert_eq!(plan.hq_ingredients[0].id, "a");
+	assert_eq!(plan.hq_ingredients[0].amount, 2);
+
+	let plan = sim
+		.optimize_hq_ingredients()
+		.options(options.clone())
+		.target(QualityTarget::Quality(300))
+		.run()
+		.expect("the target is reachable");
+	assert_eq!(plan.cost, 40);
+	assert_eq!(plan.quality, 350);
+
+	let limited = vec![
+		HqIngredientOption {
+			available: Some(1),
+			..options[0].clone()
+		},
+		options[1].clone(),
+	];
+	let plan = sim
+		.optimize_hq_ingredients()
+		.options(limited)
+		.target(QualityTarget::Quality(200))
+		.run()
+		.expect("the target is reachable");
+	assert_eq!(plan.cost, 30);
+
+	// no more HQ units can be used than the recipe needs
+	let plenty = vec![
+		HqIngredientOption {
+			available: Some(5),
+			..options[0].clone()
+		},
+		options[1].clone(),
+	];
+	let plan = sim
+		.optimize_hq_ingredients()
+		.options(plenty)
+		.target(QualityTarget::Quality(300))
+		.run()
+		.expect("the target is reachable");
+	assert_eq!(plan.cost, 40);
+
+	assert!(sim
+		.optimize_hq_ingredients()
+		.options(options.clone())
+		.target(QualityTarget::Quality(451))
+		.run()
+		.is_none());
+
+	// the quality doesn't count if the craft fails
+	let unfinished = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(generate_stats(90, 2763, 2780, 545))
+		.build();
+	assert!(unfinished
+		.optimize_hq_ingredients()
+		.options(options.clone())
+		.target(QualityTarget::Quality(200))
+		.run()
+		.is_none());
+
+	// the rotation's own quality counts towards the target, under the simulation's conditions
+	let mut rotation = vec![actions::BasicTouch.into()];
+	rotation.extend(finish);
+	let sim = Simulation::builder()
+		.recipe(recipe)
+		.crafter_stats(generate_stats(90, 2763, 2780, 545))
+		.actions(rotation)
+		.step_states(vec![StepState::Good])
+		.build();
+	let touch_quality = sim.clone().start().linear(true).run().simulation.quality;
+	let plan = sim
+		.optimize_hq_ingredients()
+		.options(options)
+		.target(QualityTarget::Quality(touch_quality + 100))
+		.run()
+		.expect("the target is reachable");
+	assert_eq!(plan.cost, 10);
+	assert_eq!(plan.quality, touch_quality + 100);
+
+	Ok(())
+}
+
+#[test]
+fn test_item_level_starting_quality() -> Result<()> {
+	let ingredient = |id: &str, amount, item_level, can_be_hq| Ingredient {
+		id: id.to_string(),
+		amount,
+		item_level: Some(item_level),
+		can_be_hq: Some(can_be_hq),
+		..Default::default()
+	};
+	let recipe = Craft {
+		material_quality_factor: Some(75),
+		ingredients: vec![
+			ingredient("a", 2, 560, true),
+			ingredient("b", 1, 555, true),
+			ingredient("crystal", 8, 1, false),
+		],
+		..generate_recipe_rlvl(3864, 80, 517, 80, 1200, 5200, 121, 105)
+	};
+	let hq = |id: &str, amount| Ingredient {
+		id: id.to_string(),
+		amount,
+		..Default::default()
+	};
+
+	// 5200 * 75% * (2 * 560) / (2 * 560 + 555), crystals can't be HQ
+	assert_eq!(recipe.starting_quality(&[hq("a", 2)]), 2607);
+	// more HQ units than the recipe uses count as the recipe's amount
+	assert_eq!(recipe.starting_quality(&[hq("a", 5)]), 2607);
+	assert_eq!(recipe.starting_quality(&[hq("a", 2), hq("b", 1)]), 3900);
+	assert_eq!(recipe.starting_quality(&[hq("crystal", 8)]), 0);
+
+	let sim = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(generate_stats(90, 2763, 2780, 545))
+		.hq_ingredients(vec![hq("b", 1)])
+		.actions(vec![actions::BasicSynthesis.into(); 5])
+		.build();
+	assert_eq!(sim.quality, 1292);
+
+	let plan = sim
+		.optimize_hq_ingredients()
+		.options(vec![
+			HqIngredientOption {
+				id: "a".to_string(),
+				cost: 10,
+				available: None,
+			},
+			HqIngredientOption {
+				id: "b".to_string(),
+				cost: 30,
+				available: None,
+			},
+		])
+		.target(QualityTarget::Quality(2000))
+		.run()
+		.expect("the target is reachable");
+	assert_eq!(plan.cost, 20);
+	assert_eq!(plan.starting_quality, 2607);
+
+	Ok(())
+}
+
+#[test]
+fn test_consumables() -> Result<()> {
+	let food = Consumable {
+		name: "Test Food".to_string(),
+		nq: ConsumableBonuses {
+			control: Some(StatBonus {
+				percent: 4,
+				max: 70,
+			}),
+			cp: Some(StatBonus {
+				percent: 21,
+				max: 70,
+			}),
+			..Default::default()
+		},
+		hq: ConsumableBonuses {
+			control: Some(StatBonus {
+				percent: 5,
+				max: 84,
+			}),
+			cp: Some(StatBonus {
+				percent: 26,
+				max: 86,
+			}),
+			..Default::default()
+		},
+	};
+	let medicine = Consumable {
+		name: "Test Medicine".to_string(),
+		nq: ConsumableBonuses {
+			craftsmanship: Some(StatBonus {
+				percent: 3,
+				max: 60,
+			}),
+			..Default::default()
+		},
+		hq: ConsumableBonuses {
+			craftsmanship: Some(StatBonus {
+				percent: 4,
+				max: 100,
+			}),
+			..Default::default()
+		},
+	};
+
+	let base = generate_stats(90, 2763, 2780, 545);
+	let stats = base.with_consumables(Some((&food, true)), Some((&medicine, false)));
+	assert_eq!(stats.craftsmanship, 2763 + 60);
+	assert_eq!(stats.control, 2780 + 84);
+	assert_eq!(stats.cp, 545 + 86);
+	// below the cap, the bonus is the percentage of the base stat
+	let stats = generate_stats(90, 1000, 1000, 200).with_consumables(Some((&food, false)), None);
+	assert_eq!(stats.control, 1040);
+	assert_eq!(stats.cp, 242);
+
+	// just out of reach without a craftsmanship bonus
+	let rotation: Vec<CraftingActionEnum> = vec![
+		actions::BasicSynthesis.into(),
+		actions::BasicSynthesis.into(),
+	];
+	let recipe = generate_recipe_rlvl(3864, 80, 517, 80, 1200, 5200, 121, 105);
+	let base_progress = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(base.clone())
+		.actions(rotation.clone())
+		.build()
+		.start()
+		.linear(true)
+		.run()
+		.simulation
+		.progression;
+	let recipe = Craft {
+		progress: base_progress + 1,
+		ingredients: vec![Ingredient {
+			id: "a".to_string(),
+			amount: 2,
+			quality: Some(100),
+			..Default::default()
+		}],
+		..recipe
+	};
+	let sim = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(base.clone())
+		.actions(rotation.clone())
+		.build();
+
+	let foods = [food];
+	let medicines = [medicine];
+	let plan = sim
+		.compare_consumables()
+		.foods(&foods)
+		.medicines(&medicines)
+		.run()
+		.expect("the medicine is enough");
+	assert!(plan.food.is_none());
+	assert_eq!(
+		plan.medicine,
+		Some(SelectedConsumable {
+			name: "Test Medicine".to_string(),
+			hq: false,
+		})
+	);
+	assert!(sim
+		.compare_consumables()
+		.foods(&foods)
+		.medicines(&[])
+		.run()
+		.is_none());
+
+	// the starting quality of HQ ingredients is kept
+	let sim = Simulation::builder()
+		.recipe(recipe)
+		.crafter_stats(base)
+		.actions(rotation)
+		.hq_ingredients(vec![Ingredient {
+			id: "a".to_string(),
+			amount: 2,
+			..Default::default()
+		}])
+		.build();
+	let plan = sim
+		.compare_consumables()
+		.foods(&foods)
+		.medicines(&medicines)
+		.target(QualityTarget::Quality(200))
+		.run()
+		.expect("the medicine is enough");
+	assert_eq!(plan.quality, 200);
+
+	Ok(())
+}
+
+#[test]
+fn test_gearset_stats() -> Result<()> {
+	let data = GearData {
+		items: vec![
+			GearItem {
+				id: "saw".to_string(),
+				name: "Splendorous Saw".to_string(),
+				craftsmanship: 5000,
+				control: 2400,
+				materia_slots: 2,
+				splendorous: true,
+				caps: Stats {
+					craftsmanship: 5040,
+					control: 2440,
+					cp: 0,
+				},
+				..Default::default()
+			},
+			GearItem {
+				id: "ring".to_string(),
+				name: "Ring".to_string(),
+				control: 300,
+				cp: 10,
+				materia_slots: 1,
+				overmeld: true,
+				caps: Stats {
+					control: 400,
+					cp: 20,
+					..Default::default()
+				},
+				..Default::default()
+			},
+		],
+		materia: vec![
+			Materia {
+				id: "cms".to_string(),
+				stat: Stat::Craftsmanship,
+				value: 54,
+				grade: 12,
+				max_overmeld: None,
+			},
+			Materia {
+				id: "ctl".to_string(),
+				stat: Stat::Control,
+				value: 54,
+				grade: 12,
+				max_overmeld: None,
+			},
+			Materia {
+				id: "cp".to_string(),
+				stat: Stat::Cp,
+				value: 11,
+				grade: 12,
+				max_overmeld: None,
+			},
+		],
+	};
+	let gearset = Gearset {
+		items: vec![
+			EquippedItem {
+				item: "saw".to_string(),
+				materia: vec!["cms".to_string(), "ctl".to_string()],
+			},
+			EquippedItem {
+				item: "ring".to_string(),
+				materia: vec!["cp".to_string(), "ctl".to_string(), "ctl".to_string()],
+			},
+		],
+	};
+
+	let base = CrafterStats {
+		cp: 180,
+		..generate_stats(100, 0, 0, 0)
+	};
+	let stats = gearset.crafter_stats(&data, &base)?;
+	// craftsmanship capped at 5040, control on the ring capped at 400, CP at 20
+	assert_eq!(stats.craftsmanship, 5040);
+	assert_eq!(stats.control, 2400 + 40 + 400);
+	assert_eq!(stats.cp, 180 + 20);
+	assert!(stats.splendorous);
+
+	assert_eq!(
+		gearset.materia_slots(&data)?,
+		vec![
+			vec![MateriaSlot::Guaranteed, MateriaSlot::Guaranteed],
+			vec![
+				MateriaSlot::Guaranteed,
+				MateriaSlot::Overmeld(1),
+				MateriaSlot::Overmeld(2)
+			],
+		]
+	);
+
+	let too_many = Gearset {
+		items: vec![EquippedItem {
+			item: "saw".to_string(),
+			materia: vec!["cms".to_string(); 3],
+		}],
+	};
+	assert!(matches!(
+		too_many.crafter_stats(&data, &base),
+		Err(GearError::TooManyMateria { slots: 2, .. })
+	));
+	let unknown = Gearset {
+		items: vec![EquippedItem {
+			item: "hat".to_string(),
+			materia: vec![],
+		}],
+	};
+	assert!(matches!(
+		unknown.crafter_stats(&data, &base),
+		Err(GearError::UnknownItem(_))
+	));
+
+	Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_gear_data_from_file() -> Result<()> {
+	let json = r#"{
+		"items": [
+			{ "id": "saw", "name": "Splendorous Saw", "craftsmanship": 5000, "control": 2400,
+			  "materia_slots": 2, "splendorous": true, "caps": { "craftsmanship": 5040 } }
+		],
+		"materia": [
+			{ "id": "cms", "stat": "Craftsmanship", "value": 54, "grade": 12 },
+			{ "id": "cp", "stat": "Cp", "value": 11, "grade": 12 }
+		]
+	}"#;
+	let path = std::env::temp_dir().join(format!("byregox-gear-{}.json", std::process::id()));
+	std::fs::write(&path, json)?;
+	let data = GearData::load(&path);
+	std::fs::remove_file(&path)?;
+	let data = data?;
+
+	let gearset = Gearset {
+		items: vec![EquippedItem {
+			item: "saw".to_string(),
+			materia: vec!["cms".to_string(), "cp".to_string()],
+		}],
+	};
+	let stats = gearset.crafter_stats(&data, &generate_stats(100, 0, 0, 180))?;
+	assert_eq!(stats.craftsmanship, 5040);
+	assert_eq!(stats.control, 2400);
+	// stats left out of the caps aren't limited
+	assert_eq!(stats.cp, 191);
+	assert!(stats.splendorous);
+
+	assert!(matches!(
+		GearData::from_json("{ \"items\": 3 }"),
+		Err(GearError::Json(_))
+	));
+
+	Ok(())
+}
+
+#[test]
+fn test_meld_optimizer() -> Result<()> {
+	let materia = |id: &str, stat, value, max_overmeld| Materia {
+		id: id.to_string(),
+		stat,
+		value,
+		grade: 12,
+		max_overmeld,
+	};
+	let data = GearData {
+		items: vec![
+			GearItem {
+				id: "saw".to_string(),
+				craftsmanship: 5000,
+				control: 2400,
+				materia_slots: 2,
+				caps: Stats {
+					craftsmanship: 5040,
+					control: 2500,
+					cp: 0,
+				},
+				..Default::default()
+			},
+			GearItem {
+				id: "ring".to_string(),
+				control: 300,
+				cp: 10,
+				materia_slots: 1,
+				overmeld: true,
+				caps: Stats {
+					control: 400,
+					cp: 40,
+					..Default::default()
+				},
+				..Default::default()
+			},
+		],
+		materia: vec![
+			materia("cms", Stat::Craftsmanship, 54, Some(1)),
+			materia("ctl", Stat::Control, 54, Some(1)),
+			materia("ctl_low", Stat::Control, 36, None),
+			materia("cp", Stat::Cp, 11, None),
+		],
+	};
+	let gearset = Gearset {
+		items: vec![
+			EquippedItem {
+				item: "saw".to_string(),
+				materia: vec![],
+			},
+			EquippedItem {
+				item: "ring".to_string(),
+				materia: vec![],
+			},
+		],
+	};
+	let base = generate_stats(100, 0, 0, 180);
+	let option = |materia: &str, cost| MeldOption {
+		materia: materia.to_string(),
+		cost,
+	};
+	let options = [
+		option("cms", 10),
+		option("ctl", 10),
+		option("ctl_low", 3),
+		option("cp", 5),
+	];
+	let target = Stats {
+		craftsmanship: 5040,
+		control: 2790,
+		cp: 200,
+	};
+
+	let plan = gearset
+		.optimize_melds()
+		.data(&data)
+		.base(&base)
+		.options(&options)
+		.target(target)
+		.run()?
+		.expect("the target is reachable");
+	// three cheap control materia on the ring beat a high grade one on the saw
+	assert_eq!(plan.cost, 10 + 3 * 3 + 5);
+	assert_eq!(plan.materia_count, 5);
+	let mut melded: Vec<_> = plan
+		.gearset
+		.items
+		.iter()
+		.flat_map(|item| item.materia.iter().map(String::as_str))
+		.collect();
+	melded.sort();
+	assert_eq!(melded, ["cms", "cp", "ctl_low", "ctl_low", "ctl_low"]);
+	assert!(plan.crafter_stats.craftsmanship >= target.craftsmanship);
+	assert!(plan.crafter_stats.control >= target.control);
+	assert!(plan.crafter_stats.cp >= target.cp);
+	assert_eq!(plan.gearset.crafter_stats(&data, &base)?.control, 2800);
+
+	// high grade materia only fit up to the first overmeld slot
+	let overmelded = Gearset {
+		items: vec![EquippedItem {
+			item: "ring".to_string(),
+			materia: vec!["ctl".to_string(); 3],
+		}],
+	};
+	assert!(matches!(
+		overmelded.crafter_stats(&data, &base),
+		Err(GearError::MateriaDoesNotFit { slot: 2, .. })
+	));
+
+	// past the saw's cap and two craftsmanship materia on the ring
+	let unreachable = Stats {
+		craftsmanship: 5000 + 40 + 2 * 54 + 1,
+		..target
+	};
+	assert!(gearset
+		.optimize_melds()
+		.data(&data)
+		.base(&base)
+		.options(&options)
+		.target(unreachable)
+		.run()?
+		.is_none());
+
+	Ok(())
+}
+
+#[test]
+fn test_minimum_stats() -> Result<()> {
+	let recipe = generate_recipe_rlvl(3864, 80, 517, 80, 1200, 5200, 121, 105);
+	let rotation: Vec<CraftingActionEnum> = vec![
+		actions::Veneration.into(),
+		actions::BasicSynthesis.into(),
+		actions::BasicSynthesis.into(),
+		actions::BasicSynthesis.into(),
+		actions::BasicSynthesis.into(),
+	];
+	let sim = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(generate_stats(90, 2763, 2780, 545))
+		.actions(rotation.clone())
+		.build();
+
+	let minimum = sim.minimum_stats().run().expect("the rotation succeeds");
+	assert_eq!(minimum.control, 0);
+	assert_eq!(minimum.cp, 18);
+	let succeeds = |craftsmanship| {
+		Simulation::builder()
+			.recipe(recipe.clone())
+			.crafter_stats(generate_stats(90, craftsmanship, 2780, 545))
+			.actions(rotation.clone())
+			.build()
+			.start()
+			.linear(true)
+			.run()
+			.success
+	};
+	assert!(succeeds(minimum.craftsmanship));
+	assert!(!succeeds(minimum.craftsmanship - 1));
+
+	assert!(sim
+		.minimum_stats()
+		.target(QualityTarget::Quality(5200))
+		.run()
+		.is_none());
+
+	// with enough craftsmanship the craft finishes without Veneration, so CP can only be
+	// lowered to 0 if craftsmanship isn't lowered too
+	let synthesis: Vec<CraftingActionEnum> = vec![actions::BasicSynthesis.into(); 3];
+	let progress = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(generate_stats(90, 2763, 2780, 545))
+		.actions(synthesis.clone())
+		.build()
+		.start()
+		.linear(true)
+		.run()
+		.simulation
+		.progression;
+	let recipe = Craft { progress, ..recipe };
+	let mut rotation = vec![actions::Veneration.into()];
+	rotation.extend(synthesis);
+	let minimum = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(generate_stats(90, 2763, 2780, 545))
+		.actions(rotation.clone())
+		.build()
+		.minimum_stats()
+		.run()
+		.expect("the rotation succeeds");
+	assert!(minimum.craftsmanship < 2763);
+	assert_eq!(minimum.cp, 18);
+	assert!(
+		Simulation::builder()
+			.recipe(recipe)
+			.crafter_stats(generate_stats(
+				90,
+				minimum.craftsmanship,
+				minimum.control,
+				minimum.cp
+			))
+			.actions(rotation)
+			.build()
+			.start()
+			.linear(true)
+			.run()
+			.success
+	);
+
+	Ok(())
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_recipe_database_from_sheets() -> Result<()> {
+	let recipes = "\
+key,0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17
+#,Number,CraftType,RecipeLevelTable,Item{Result},Amount{Result},Item{Ingredient}[0],Amount{Ingredient}[0],Item{Ingredient}[1],Amount{Ingredient}[1],MaterialQualityFactor,DifficultyFactor,QualityFactor,DurabilityFactor,RequiredCraftsmanship,RequiredControl,CanQuickSynth,CanHq,IsExpert
+int32,int32,CraftType,RecipeLevelTable,Item,byte,Item,byte,Item,byte,byte,uint16,uint16,uint16,uint16,uint16,bit&01,bit&02,bit&04
+0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,False,False,False
+35499,0,1,640,44110,1,44120,2,44121,3,75,100,100,100,0,0,True,True,False
+35500,0,5,690,44111,3,44122,1,0,0,50,80,90,50,4900,4800,False,True,True
+";
+	let recipe_levels = "\
+key,0,1,2,3,4,5,6,7,8,9,10
+#,ClassJobLevel,Stars,SuggestedCraftsmanship,Difficulty,Quality,ProgressDivider,QualityDivider,ProgressModifier,QualityModifier,Durability,ConditionsFlag
+int32,byte,byte,uint16,uint16,uint32,byte,byte,byte,byte,uint16,uint16
+640,90,0,3700,3900,7200,130,115,80,70,80,15
+690,100,2,4700,6600,12000,170,150,100,100,80,483
+";
+	let items = "\
+key,0,1
+#,Level{Item},CanBeHq
+int32,uint16,bool
+44120,560,True
+44121,550,False
+44122,690,True
+";
+	let dir = std::env::temp_dir().join(format!("byregox-sheets-{}", std::process::id()));
+	std::fs::create_dir_all(&dir)?;
+	std::fs::write(dir.join("Recipe.csv"), recipes)?;
+	std::fs::write(dir.join("RecipeLevelTable.csv"), recipe_levels)?;
+	std::fs::write(dir.join("Item.csv"), items)?;
+	let database = RecipeDatabase::load(&dir);
+	std::fs::remove_dir_all(&dir)?;
+	let database = database?;
+
+	// the zeroed row is skipped
+	assert_eq!(database.len(), 2);
+	assert!(database.get(0).is_none());
+
+	let recipe = database.get(35499).unwrap();
+	assert_eq!(recipe.id, "35499");
+	assert_eq!(recipe.job, 9);
+	assert_eq!(recipe.rlvl, 640);
+	assert_eq!(recipe.lvl, 90);
+	assert_eq!(recipe.progress, 3900);
+	assert_eq!(recipe.quality, 7200);
+	assert_eq!(recipe.durability, 80);
+	assert_eq!(recipe.progress_divider, 130);
+	assert_eq!(recipe.quality_modifier, Some(70.0));
+	assert_eq!(recipe.conditions_flag, 15);
+	assert_eq!(recipe.material_quality_factor, Some(75));
+	assert_eq!(recipe.quick_synth, Some(true));
+	assert_eq!(recipe.expert, Some(false));
+	assert_eq!(recipe.craftsmanship_req, None);
+	assert_eq!(recipe.ingredients.len(), 2);
+	assert_eq!(recipe.ingredients[0].id, "44120");
+	assert_eq!(recipe.ingredients[0].amount, 2);
+	assert_eq!(recipe.ingredients[0].item_level, Some(560));
+	assert_eq!(recipe.ingredients[1].can_be_hq, Some(false));
+
+	// a recipe of any level can be built from the level's row and the recipe's factors
+	let level = database.recipe_level(690).expect("690 is in the sheet");
+	let recipe = level.craft(690, 50, 80, 50);
+	assert_eq!(recipe.lvl, 100);
+	assert_eq!(recipe.stars, Some(2));
+	assert_eq!(recipe.suggested_craftsmanship, Some(4700));
+	assert_eq!(recipe.progress, 3300);
+	assert_eq!(recipe.quality, 9600);
+	assert_eq!(recipe.durability, 40);
+	assert_eq!(recipe.quality_divider, 150);
+	assert_eq!(recipe.progress_modifier, Some(100.0));
+	assert_eq!(recipe.conditions_flag, 483);
+	assert!(database.recipe_level(1).is_none());
+
+	let expert = database.get(35500).unwrap();
+	assert_eq!(expert.job, 13);
+	assert_eq!(expert.r#yield, Some(3));
+	assert_eq!(expert.progress, 5280);
+	assert_eq!(expert.quality, 10800);
+	assert_eq!(expert.durability, 40);
+	assert_eq!(expert.expert, Some(true));
+	assert_eq!(expert.craftsmanship_req, Some(4900));
+	assert_eq!(expert.control_req, Some(4800));
+	assert_eq!(expert.ingredients.len(), 1);
+
+	// only the HQ-able ingredient adds quality: 10800 * 50% * 690 / 690
+	assert_eq!(
+		expert.starting_quality(&[Ingredient {
+			id: "44122".to_string(),
+			amount: 1,
+			..Default::default()
+		}]),
+		5400
+	);
+
+	Ok(())
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_recipe_database_errors() -> Result<()> {
+	let recipe_levels = "\
+#,ClassJobLevel,Stars,SuggestedCraftsmanship,Difficulty,Quality,ProgressDivider,QualityDivider,ProgressModifier,QualityModifier,Durability,ConditionsFlag
+1,1,0,22,9,80,50,30,100,100,60,15
+";
+	let header = "#,CraftType,RecipeLevelTable,Item{Result},Amount{Result},MaterialQualityFactor,DifficultyFactor,QualityFactor,DurabilityFactor,RequiredCraftsmanship,RequiredControl,RequiredQuality,SecretRecipeBook,CanQuickSynth,CanHq,IsExpert";
+	let load = |row: &str| {
+		RecipeDatabase::from_readers(
+			format!("{}\n{}\n", header, row).as_bytes(),
+			recipe_levels.as_bytes(),
+			None::<&[u8]>,
+		)
+	};
+
+	// plain headers work, without an item sheet
+	let database = load("1,0,1,5056,1,0,100,100,100,0,0,0,0,False,False,False")?;
+	let recipe = database.get(1).unwrap();
+	assert_eq!(recipe.progress, 9);
+	assert_eq!(recipe.job, 8);
+	assert_eq!(recipe.material_quality_factor, Some(0));
+
+	assert!(matches!(
+		load("2,0,7,5056,1,0,100,100,100,0,0,0,0,False,False,False"),
+		Err(GameDataError::UnknownRecipeLevel {
+			recipe: 2,
+			level: 7
+		})
+	));
+	assert!(matches!(
+		load("3,0,1,5056,1,0,100,100,100,0,0,0,0,maybe,False,False"),
+		Err(GameDataError::InvalidValue { .. })
+	));
+	assert!(matches!(
+		RecipeDatabase::from_readers(
+			"#,CraftType\n1,0\n".as_bytes(),
+			recipe_levels.as_bytes(),
+			None::<&[u8]>,
+		),
+		Err(GameDataError::MissingColumn { .. })
+	));
+
+	Ok(())
+}
+
+#[test]
+fn test_craft_from_recipe_level() -> Result<()> {
+	let recipe = Craft::from_recipe_level()
+		.rlvl(580)
+		.build()
+		.expect("580 is embedded");
+	assert_eq!(recipe.rlvl, 580);
+	assert_eq!(recipe.lvl, 90);
+	assert_eq!(recipe.stars, Some(1));
+	assert_eq!(recipe.progress, 3900);
+	assert_eq!(recipe.quality, 10920);
+	assert_eq!(recipe.durability, 70);
+	assert_eq!(recipe.conditions_flag, 15);
+
+	// same as the hand written star recipe
+	let sim = Simulation::builder()
+		.recipe(recipe)
+		.actions(vec![
+			actions::MuscleMemory.into(),
+			actions::Veneration.into(),
+			actions::Groundwork.into(),
+			actions::Groundwork.into(),
+			actions::Observe.into(),
+			actions::Observe.into(),
+			actions::CarefulSynthesis.into(),
+		])
+		.crafter_stats(generate_stats(90, 3289, 3420, 400))
+		.build();
+	let result = sim.start().linear(true).run();
+	assert_eq!(result.simulation.steps[0].added_progression, 609);
+	assert_eq!(result.simulation.progression, 3897);
+
+	let recipe = Craft::from_recipe_level()
+		.rlvl(517)
+		.progress_factor(50)
+		.quality_factor(80)
+		.durability_factor(50)
+		.build()
+		.expect("517 is embedded");
+	assert_eq!(recipe.progress, 1000);
+	assert_eq!(recipe.quality, 4160);
+	assert_eq!(recipe.durability, 40);
+	assert_eq!(recipe.progress_divider, 121);
+	assert_eq!(recipe.quality_divider, 105);
+	assert_eq!(recipe.progress_modifier, Some(100.0));
+
+	assert!(Craft::from_recipe_level().rlvl(1).build().is_none());
+	assert!(tables::recipe_level(560).is_some_and(|level| level.quality_modifier == 80));
+
+	Ok(())
+}
+
+#[test]
+fn test_trait_table() -> Result<()> {
+	assert_eq!(tables::traits(CraftingLevel::unchecked_new(10)).count(), 0);
+	assert_eq!(
+		tables::traits(CraftingLevel::unchecked_new(63)).collect::<Vec<_>>(),
+		vec![
+			CrafterTrait::InnerQuiet,
+			CrafterTrait::BasicSynthesisMastery,
+			CrafterTrait::RapidSynthesisMastery,
+			CrafterTrait::QualityAssurance,
+		]
+	);
+	assert_eq!(
+		tables::traits(CraftingLevel::max()).count(),
+		tables::TRAITS.len()
+	);
+
+	// each potency upgrade applies from its trait's level on
+	let at_level = |level| {
+		Simulation::builder()
+			.recipe(generate_recipe_lvl(3864, 16, 80, 31, 866, 50, 30))
+			.actions(vec![])
+			.crafter_stats(generate_stats(level, 1000, 1000, 400))
+			.build()
+	};
+	assert_eq!(actions::BasicSynthesis.get_potency(&at_level(30)), 100);
+	assert_eq!(actions::BasicSynthesis.get_potency(&at_level(31)), 120);
+	assert_eq!(actions::RapidSynthesis.get_potency(&at_level(62)), 250);
+	assert_eq!(actions::RapidSynthesis.get_potency(&at_level(63)), 500);
+	assert_eq!(actions::CarefulSynthesis.get_potency(&at_level(81)), 150);
+	assert_eq!(actions::CarefulSynthesis.get_potency(&at_level(82)), 180);
+	assert_eq!(actions::Groundwork.get_potency(&at_level(85)), 300);
+	assert_eq!(actions::Groundwork.get_potency(&at_level(86)), 360);
+	assert_eq!(
+		actions::DelicateSynthesis.get_progress_potency(&at_level(93)),
+		100
+	);
+	assert_eq!(
+		actions::DelicateSynthesis.get_progress_potency(&at_level(94)),
+		150
+	);
+	assert_eq!(actions::DelicateSynthesis.get_potency(&at_level(94)), 100);
+
+	// and changes what the action does, in the game versions that have it
+	let delicate = |level, game_version| {
+		Simulation::builder()
+			.recipe(generate_recipe_lvl(3864, 90, 80, 3000, 6000, 130, 115))
+			.actions(vec![actions::DelicateSynthesis.into()])
+			.crafter_stats(generate_stats(level, 4000, 4000, 600))
+			.game_version(game_version)
+			.build()
+			.start()
+			.linear(true)
+			.run()
+			.simulation
+	};
+	let before = delicate(93, GameVersion::Dawntrail);
+	let after = delicate(94, GameVersion::Dawntrail);
+	assert_eq!(after.progression, before.progression * 3 / 2);
+	assert_eq!(after.quality, before.quality);
+	let endwalker = delicate(94, GameVersion::Endwalker);
+	assert_eq!(endwalker.progression, before.progression);
+
+	// quality actions only grant Inner Quiet from level 11
+	for (level, stacks) in [(10, 0), (11, 1)] {
+		let result = Simulation::builder()
+			.recipe(generate_recipe_lvl(3864, 5, 80, 9, 80, 50, 30))
+			.actions(vec![actions::BasicTouch.into()])
+			.crafter_stats(generate_stats(level, 100, 100, 200))
+			.build()
+			.start()
+			.linear(true)
+			.run();
+		assert_eq!(
+			result
+				.simulation
+				.get_buff(Buff::InnerQuiet)
+				.map_or(0, |buff| buff.stacks),
+			stacks
+		);
+	}
+
+	// Quality Assurance raises the chance of a Good condition from level 63
+	let good_chance = |level| {
+		at_level(level)
+			.next_state_distribution()
+			.into_iter()
+			.find(|&(state, _)| state == StepState::Good)
+			.map(|(_, rate)| rate)
+	};
+	assert_eq!(good_chance(62), Some(0.2));
+	assert_eq!(good_chance(63), Some(0.25));
+
+	// Hasty Touch only grants Expedience once its trait is learned
+	for (level, expedience) in [(95, false), (96, true)] {
+		let sim = Simulation::builder()
+			.recipe(generate_recipe_lvl(3864, 90, 80, 3000, 6000, 130, 115))
+			.actions(vec![actions::HastyTouch.into()])
+			.crafter_stats(generate_stats(level, 4000, 4000, 600))
+			.build();
+		let result = sim.start().linear(true).run();
+		assert_eq!(result.simulation.has_buff(Buff::Expedience), expedience);
+		assert!(result.simulation.has_trait(CrafterTrait::InnerQuiet));
+	}
+
+	Ok(())
+}
+
+#[test]
+fn test_game_versions() -> Result<()> {
+	let run = |game_version, actions: Vec<CraftingActionEnum>| {
+		Simulation::builder()
+			.recipe(generate_recipe_lvl(3864, 90, 80, 6000, 12000, 130, 115))
+			.actions(actions)
+			.crafter_stats(generate_stats(90, 4000, 4000, 600))
+			.game_version(game_version)
+			.build()
+			.start()
+			.linear(true)
+			.run()
+	};
+
+	// Reflect was buffed from 100 to 300 potency
+	let endwalker = run(GameVersion::Endwalker, vec![actions::Reflect.into()]);
+	let dawntrail = run(GameVersion::Dawntrail, vec![actions::Reflect.into()]);
+	assert_eq!(endwalker.simulation.quality, 382);
+	assert_eq!(dawntrail.simulation.quality, 382 * 3);
+
+	// Observe combos into Focused Touch in Endwalker, and into Advanced Touch since
+	let observe_then = |game_version, action: CraftingActionEnum| {
+		run(game_version, vec![actions::Observe.into(), action])
+	};
+	let result = observe_then(GameVersion::Endwalker, actions::FocusedTouch.into());
+	assert_eq!(result.simulation.steps[1].success, Some(true));
+	assert_eq!(result.simulation.steps[1].cp_difference, -18);
+	let result = observe_then(GameVersion::Dawntrail, actions::FocusedTouch.into());
+	assert!(result.simulation.steps[1].skipped);
+	assert_eq!(
+		result.simulation.steps[1].fail_cause,
+		Some(FailCause::UnavailableInGameVersion)
+	);
+	let result = observe_then(GameVersion::Endwalker, actions::AdvancedTouch.into());
+	assert_eq!(result.simulation.steps[1].cp_difference, -46);
+	let result = observe_then(GameVersion::Dawntrail, actions::AdvancedTouch.into());
+	assert_eq!(result.simulation.steps[1].cp_difference, -18);
+
+	// Focused actions are a coin flip without Observe
+	let sim = Simulation::builder()
+		.recipe(generate_recipe_lvl(3864, 90, 80, 6000, 12000, 130, 115))
+		.actions(vec![])
+		.crafter_stats(generate_stats(90, 4000, 4000, 600))
+		.game_version(GameVersion::Endwalker)
+		.build();
+	assert_eq!(actions::FocusedSynthesis.get_success_rate(&sim), 50);
+
+	// Dawntrail actions don't exist in Endwalker
+	let dawntrail_actions: [CraftingActionEnum; 5] = [
+		actions::DaringTouch.into(),
+		actions::RefinedTouch.into(),
+		actions::ImmaculateMend.into(),
+		actions::QuickInnovation.into(),
+		actions::TrainedPerfection.into(),
+	];
+	for action in dawntrail_actions {
+		assert!(!action.can_be_used(&sim));
+		assert_eq!(
+			action.get_fail_cause(&sim),
+			Some(FailCause::UnavailableInGameVersion)
+		);
+	}
+
+	// neither does the Hasty Touch trait, whatever the crafter's level
+	let result = Simulation::builder()
+		.recipe(generate_recipe_lvl(3864, 90, 80, 6000, 12000, 130, 115))
+		.actions(vec![actions::HastyTouch.into()])
+		.crafter_stats(generate_stats(100, 4000, 4000, 600))
+		.game_version(GameVersion::Endwalker)
+		.build()
+		.start()
+		.linear(true)
+		.run();
+	assert!(!result.simulation.has_buff(Buff::Expedience));
+
+	Ok(())
+}
+
+#[test]
+fn test_condition_set() -> Result<()> {
+	let expert = ConditionSet::from(483);
+	assert_eq!(
+		expert.iter().collect::<Vec<_>>(),
+		vec![
+			StepState::Normal,
+			StepState::Good,
+			StepState::Sturdy,
+			StepState::Pliant,
+			StepState::Malleable,
+			StepState::Primed,
+		]
+	);
+	assert!(expert.contains(StepState::Primed));
+	assert!(!expert.contains(StepState::Excellent));
+	assert!(!expert.contains(StepState::None));
+	assert_eq!(u32::from(expert.iter().collect::<ConditionSet>()), 483);
+
+	let mut standard: ConditionSet = [StepState::Normal, StepState::Good].into_iter().collect();
+	standard.insert(StepState::Excellent);
+	standard.insert(StepState::Poor);
+	standard.insert(StepState::None);
+	assert_eq!(standard, ConditionSet::STANDARD);
+	assert_eq!(
+		generate_recipe_lvl(3864, 90, 80, 6000, 12000, 130, 115).conditions(),
+		ConditionSet::STANDARD
+	);
+
+	Ok(())
+}
+
+#[test]
+fn test_condition_rates() -> Result<()> {
+	let recipe = Craft {
+		expert: Some(true),
+		conditions_flag: 483,
+		..generate_recipe_rlvl(3864, 80, 480, 80, 6178, 36208, 110, 90)
+	};
+	let stats = generate_stats(80, 2745, 2885, 626);
+	let sim = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(stats.clone())
+		.build();
+	let distribution = sim.next_state_distribution();
+	assert_eq!(
+		distribution,
+		vec![
+			(StepState::Good, 0.12),
+			(StepState::Sturdy, 0.15),
+			(StepState::Pliant, 0.12),
+			(StepState::Malleable, 0.12),
+			(StepState::Primed, 0.12),
+			(StepState::Normal, 1.0 - 0.63),
+		]
+	);
+
+	// replacing the expert rates doesn't touch the standard ones
+	let mut rates = ConditionRates::default();
+	rates.expert.sturdy = 0.5;
+	rates.expert.primed = 0.0;
+	let mut sim = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(stats.clone())
+		.condition_rates(rates)
+		.build();
+	assert_eq!(sim.condition_rates().standard.sturdy, 0.15);
+	let distribution: HashMap<_, _> = sim.next_state_distribution().into_iter().collect();
+	assert_eq!(distribution[&StepState::Sturdy], 0.5);
+	assert_eq!(distribution[&StepState::Primed], 0.0);
+	let total: f64 = distribution.values().sum();
+	assert!((total - 1.0).abs() < 1e-9);
+
+	sim.override_state(StepState::Excellent);
+	assert_eq!(sim.next_state_distribution(), vec![(StepState::Poor, 1.0)]);
+
+	// rates adding up past 1 are scaled down instead of giving Normal a negative chance
+	let mut rates = ConditionRates::default();
+	rates.expert.sturdy = 0.9;
+	let sim = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(stats.clone())
+		.condition_rates(rates)
+		.build();
+	let distribution: HashMap<_, _> = sim.next_state_distribution().into_iter().collect();
+	assert_eq!(distribution[&StepState::Normal], 0.0);
+	assert!((distribution[&StepState::Sturdy] - 0.9 / 1.38).abs() < 1e-9);
+	let total: f64 = distribution.values().sum();
+	assert!((total - 1.0).abs() < 1e-9);
+
+	// a class of recipes can have rates of its own
+	let mut rates = ConditionRates::default();
+	rates.classes.push((
+		recipe.conditions(),
+		RecipeConditionRates {
+			pliant: 0.3,
+			..rates.expert
+		},
+	));
+	let with_rates = |recipe| {
+		Simulation::builder()
+			.recipe(recipe)
+			.crafter_stats(stats.clone())
+			.condition_rates(rates.clone())
+			.build()
+			.next_state_distribution()
+			.into_iter()
+			.collect::<HashMap<_, _>>()
+	};
+	assert_eq!(with_rates(recipe.clone())[&StepState::Pliant], 0.3);
+	let other_class = Craft {
+		conditions_flag: 115,
+		..recipe.clone()
+	};
+	assert_eq!(with_rates(other_class)[&StepState::Pliant], 0.12);
+
+	// standard recipes get a better Good chance with Quality Assurance
+	let standard = |level| {
+		Simulation::builder()
+			.recipe(generate_recipe_lvl(3864, 50, 80, 6000, 12000, 130, 115))
+			.crafter_stats(generate_stats(level, 2745, 2885, 626))
+			.build()
+			.next_state_distribution()
+	};
+	assert_eq!(standard(62)[0], (StepState::Good, 0.2));
+	assert_eq!(standard(63)[0], (StepState::Good, 0.25));
+
+	Ok(())
+}
+
+#[test]
+fn test_script_parsing() -> Result<()> {
+	let text = "MuscleMemory # opener
+Manipulation
+repeat until progress >= max or durability <= 10 {
+	if state == Good {
+		TricksOfTheTrade
+	} else if state != Sturdy and (cp > 18 or buff WasteNot) {
+		CarefulSynthesis
+	} else {
+		Groundwork
+	}
+}
+repeat 2 {
+	BasicTouch
+}
+if stacks InnerQuiet >= 10 and not buff Innovation {
+	Innovation
+}
+ByregotsBlessing
+";
+	let script = Script::parse(text)?;
+	assert_eq!(script.steps.len(), 6);
+	assert_eq!(
+		script.steps[3],
+		ScriptStep::RepeatTimes {
+			times: 2,
+			steps: vec![ScriptStep::Action(actions::BasicTouch.into())],
+		}
+	);
+	assert_eq!(
+		script.steps[4],
+		ScriptStep::If {
+			condition: ScriptCondition::And(
+				Box::new(ScriptCondition::Stacks(
+					Buff::InnerQuiet,
+					Comparison::GreaterOrEqual,
+					10
+				)),
+				Box::new(ScriptCondition::Not(Box::new(ScriptCondition::Buff(
+					Buff::Innovation
+				)))),
+			),
+			then: vec![ScriptStep::Action(actions::Innovation.into())],
+			otherwise: vec![],
+		}
+	);
+
+	// the canonical text parses back to the same script
+	let displayed = script.to_string();
+	assert_eq!(displayed.parse::<Script>()?, script);
+	assert_eq!(Script::parse(&displayed)?.to_string(), displayed);
+
+	assert_eq!(
+		Script::parse("BasicTouch\nBrandOfWind"),
+		Err(ScriptParseError::UnknownAction {
+			line: 1,
+			name: "BrandOfWind".to_string(),
+		})
+	);
+	assert_eq!(
+		Script::parse("if buff Veneration {\n\tBasicTouch"),
+		Err(ScriptParseError::UnexpectedEnd { expected: "}" })
+	);
+	assert_eq!(
+		Script::parse("if state == Bad { BasicTouch }"),
+		Err(ScriptParseError::UnknownState {
+			line: 0,
+			name: "Bad".to_string(),
+		})
+	);
+	assert_eq!(
+		Script::parse("repeat many { BasicTouch }"),
+		Err(ScriptParseError::Unexpected {
+			line: 0,
+			found: "many".to_string(),
+			expected: "a number of times or `until`",
+		})
+	);
+	assert_eq!(
+		Script::parse("BasicTouch\n}"),
+		Err(ScriptParseError::Unexpected {
+			line: 1,
+			found: "}".to_string(),
+			expected: "an action, `if` or `repeat`",
+		})
+	);
+
+	Ok(())
+}
+
+#[test]
+fn test_script_run() -> Result<()> {
+	let recipe = generate_recipe_rlvl(3864, 80, 517, 80, 1200, 5200, 121, 105);
+	let stats = generate_stats(90, 2763, 2780, 545);
+	let rotation: Vec<CraftingActionEnum> = vec![
+		actions::Reflect.into(),
+		actions::BasicTouch.into(),
+		actions::StandardTouch.into(),
+		actions::CarefulSynthesis.into(),
+		actions::CarefulSynthesis.into(),
+	];
+	let sim = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(stats.clone())
+		.actions(rotation.clone())
+		.build();
+
+	// a script without branches is the same as the rotation
+	let scripted = sim
+		.clone()
+		.run_script()
+		.script(&Script::from(rotation.clone()))
+		.linear(true)
+		.run();
+	let started = sim.start().linear(true).run();
+	assert_eq!(scripted.simulation.actions, rotation);
+	assert_eq!(scripted.success, started.success);
+	assert_eq!(scripted.simulation.quality, started.simulation.quality);
+
+	let script = Script::parse(
+		"repeat until finished {
+			if state == Good {
+				TricksOfTheTrade
+			} else {
+				BasicSynthesis
+			}
+		}",
+	)?;
+	let result = Simulation::builder()
+		.recipe(recipe)
+		.crafter_stats(stats)
+		.step_states(vec![
+			StepState::Normal,
+			StepState::Good,
+			StepState::Normal,
+			StepState::Good,
+		])
+		.build()
+		.run_script()
+		.script(&script)
+		.linear(true)
+		.run();
+	assert!(result.success);
+	assert_eq!(
+		result.simulation.actions[..4],
+		[
+			actions::BasicSynthesis.into(),
+			actions::TricksOfTheTrade.into(),
+			actions::BasicSynthesis.into(),
+			actions::TricksOfTheTrade.into(),
+		]
+	);
+	assert!(result.simulation.actions[4..]
+		.iter()
+		.all(|action| *action == actions::BasicSynthesis.into()));
+
+	// loops whose condition is never met stop at max_steps
+	let result = Simulation::builder()
+		.recipe(generate_recipe_rlvl(
+			3864, 80, 517, 80, 1200, 5200, 121, 105,
+		))
+		.crafter_stats(generate_stats(90, 2763, 2780, 545))
+		.build()
+		.run_script()
+		.script(&Script::parse(
+			"repeat until quality >= 999999 { Observe }",
+		)?)
+		.linear(true)
+		.max_steps(5)
+		.run();
+	assert_eq!(result.simulation.actions.len(), 5);
+	assert!(!result.success);
+
+	// and so do counted loops, which also stop once the craft is finished or a pass uses
+	// no action
+	let run = |script: &str| -> Result<SimulationResult> {
+		Ok(Simulation::builder()
+			.recipe(generate_recipe_rlvl(
+				3864, 80, 517, 80, 1200, 5200, 121, 105,
+			))
+			.crafter_stats(generate_stats(90, 2763, 2780, 545))
+			.build()
+			.run_script()
+			.script(&Script::parse(script)?)
+			.linear(true)
+			.run())
+	};
+	let result = run("repeat 4000000000 { BasicSynthesis }")?;
+	assert!(result.success);
+	assert!(result.simulation.actions.len() < 10);
+	let result = run("repeat 4000000000 { if state == Excellent { Observe } }
+		BasicTouch")?;
+	assert_eq!(result.simulation.actions, [actions::BasicTouch.into()]);
+
+	Ok(())
+}
+
+#[test]
+fn test_policy_runs() -> Result<()> {
+	// generateRecipe(480, 6178, 36208, 110, 90, 483)
+	let recipe = Craft {
+		expert: Some(true),
+		conditions_flag: 483,
+		..generate_recipe_rlvl(3864, 80, 480, 80, 1500, 36208, 110, 90)
+	};
+	let stats = generate_stats(90, 2745, 2885, 626);
+	let sim = Simulation::builder()
+		.recipe(recipe)
+		.crafter_stats(stats)
+		.seed(11)
+		.build();
+	let heuristic = |sim: &Simulation| -> CraftingActionEnum {
+		if sim.state() == StepState::Good {
+			actions::TricksOfTheTrade.into()
+		} else if sim.durability <= 10 {
+			actions::MastersMend.into()
+		} else {
+			actions::BasicSynthesis.into()
+		}
+	};
+
+	let result = sim
+		.clone()
+		.run_policy()
+		.policy(&mut heuristic.clone())
+		.run();
+	assert!(result.success);
+	assert!(result
+		.steps
+		.iter()
+		.filter(|step| step.action == actions::TricksOfTheTrade.into())
+		.all(|step| step.state == StepState::Good));
+	let again = sim
+		.clone()
+		.run_policy()
+		.policy(&mut heuristic.clone())
+		.run();
+	assert_eq!(result.simulation.actions, again.simulation.actions);
+
+	let single_threaded = sim
+		.policy_batch()
+		.policy(&heuristic)
+		.runs(200)
+		.seed(3)
+		.threads(1)
+		.run();
+	let multi_threaded = sim
+		.policy_batch()
+		.policy(&heuristic)
+		.runs(200)
+		.seed(3)
+		.threads(4)
+		.run();
+	assert_eq!(single_threaded.successes, 200);
+	assert_eq!(
+		single_threaded.progression.samples(),
+		multi_threaded.progression.samples()
+	);
+
+	// a policy that never finishes the craft is stopped after max_steps
+	let stalled = sim
+		.policy_batch()
+		.policy(&|_: &Simulation| -> CraftingActionEnum { actions::Observe.into() })
+		.runs(10)
+		.seed(3)
+		.max_steps(5)
+		.run();
+	assert_eq!(stalled.unfinished, 10);
+	assert_eq!(stalled.average_final_step, 5.0);
+
+	Ok(())
+}
+
+#[test]
+fn test_rotation_lint() -> Result<()> {
+	let recipe = generate_recipe_rlvl(3864, 80, 517, 80, 1200, 5200, 121, 105);
+	let stats = generate_stats(90, 2763, 2780, 545);
+	let result = Simulation::builder()
+		.recipe(recipe.clone())
+		.crafter_stats(stats.clone())
+		.actions(vec![
+			actions::Reflect.into(),
+			actions::Veneration.into(),
+			actions::BasicTouch.into(),
+			actions::Manipulation.into(),
+			actions::MastersMend.into(),
+			actions::Manipulation.into(),
+			actions::Observe.into(),
+			actions::BasicSynthesis.into(),
+			actions::CarefulSynthesis.into(),
+			actions::CarefulSynthesis.into(),
+			actions::CarefulSynthesis.into(),
+		])
+		.build()
+		.start()
+		.linear(true)
+		.run();
+	assert_eq!(
+		result.lint(),
+		vec![
+			LintWarning {
+				step: 1,
+				kind: LintKind::UnusedBuff {
+					buff: Buff::Veneration
+				},
+			},
+			// Master's Mend at 60 of 80 durability, then Manipulation at full durability
+			LintWarning {
+				step: 4,
+				kind: LintKind::WastedRepair { wasted: 15 },
+			},
+			LintWarning {
+				step: 5,
+				kind: LintKind::ManipulationRecast { remaining: 7 },
+			},
+			LintWarning {
+				step: 6,
+				kind: LintKind::WastedRepair { wasted: 5 },
+			},
+			LintWarning {
+				step: 6,
+				kind: LintKind::ObserveWithoutCombo,
+			},
+			LintWarning {
+				step: 7,
+				kind: LintKind::ProgressOvershoot {
+					progression: 276,
+					excess: 318,
+				},
+			},
+			LintWarning {
+				step: 10,
+				kind: LintKind::LeftoverCp { cp: 195 },
+			},
+		]
+	);
+
+	let result = Simulation::builder()
+		.recipe(Craft {
+			durability: 200,
+			..recipe
+		})
+		.crafter_stats(stats)
+		.actions(vec![
+			actions::Reflect.into(),
+			actions::Innovation.into(),
+			actions::PreparatoryTouch.into(),
+			actions::PreparatoryTouch.into(),
+			actions::PreparatoryTouch.into(),
+			actions::PreparatoryTouch.into(),
+			actions::PreparatoryTouch.into(),
+			actions::Observe.into(),
+			actions::AdvancedTouch.into(),
+			// a touch without an extra stack is still worth its quality at 10 stacks
+			actions::BasicTouch.into(),
+		])
+		.build()
+		.start()
+		.linear(true)
+		.run();
+	assert_eq!(
+		result
+			.simulation
+			.get_buff(Buff::InnerQuiet)
+			.map(|b| b.stacks),
+		Some(10)
+	);
+	let warnings = result.lint();
+	assert_eq!(
+		warnings,
+		vec![LintWarning {
+			step: 6,
+			kind: LintKind::InnerQuietCapped,
+		}]
+	);
+	assert_eq!(
+		warnings[0].to_string(),
+		"step 7: Inner Quiet is already at 10 stacks (use a cheaper touch, as the extra stack \
+		 is lost at 10 stacks)"
+	);
+
+	Ok(())
+}
+
+#[test]
+fn test_rotation_comparison() -> Result<()> {
+	let recipe = generate_recipe_rlvl(3864, 80, 517, 80, 1200, 5200, 121, 105);
+	let stats = generate_stats(90, 2763, 2780, 545);
+	let sim = Simulation::builder()
+		.recipe(recipe)
+		.crafter_stats(stats)
+		.build();
+	let comparison = sim
+		.compare()
+		.baseline(vec![
+			actions::Reflect.into(),
+			actions::BasicTouch.into(),
+			actions::StandardTouch.into(),
+			actions::CarefulSynthesis.into(),
+			actions::CarefulSynthesis.into(),
+			actions::CarefulSynthesis.into(),
+		])
+		.candidate(vec![
+			actions::Reflect.into(),
+			actions::Innovation.into(),
+			actions::BasicTouch.into(),
+			actions::StandardTouch.into(),
+			actions::CarefulSynthesis.into(),
+			actions::CarefulSynthesis.into(),
+			actions::CarefulSynthesis.into(),
+		])
+		.run();
+	assert_eq!(comparison.baseline.quality, 1740);
+	assert_eq!(comparison.candidate.quality, 2163);
+	assert_eq!(
+		comparison.difference(),
+		RotationDifference {
+			quality: 423,
+			hq_percent: 3,
+			cp_left: -18,
+			durability_left: 0,
+			steps: 1,
+			macro_duration: 2,
+		}
+	);
+
+	// Innovation is lined up against nothing, and the touches after it gain quality
+	assert_eq!(comparison.steps.len(), 7);
+	assert!(comparison.steps[1].baseline.is_none());
+	assert_eq!(
+		comparison.steps[1]
+			.candidate
+			.as_ref()
+			.map(|step| step.action),
+		Some(actions::Innovation.into())
+	);
+	assert_eq!(comparison.first_quality_difference, Some(2));
+	let row = &comparison.steps[2];
+	assert_eq!(row.baseline.as_ref().map(|step| step.index), Some(1));
+	assert_eq!(row.candidate.as_ref().map(|step| step.index), Some(2));
+
+	let text = comparison.to_string();
+	assert!(text.contains("quality                1740       2163       +423"));
+	assert!(text.contains("macro time (s)           18         20         +2"));
+	assert!(text.ends_with(
+		"> quality first differs after step 2 of the baseline (1255) and step 3 of the \
+		 candidate (1435)"
+	));
+
+	let same = sim
+		.compare()
+		.baseline(vec![actions::BasicSynthesis.into()])
+		.candidate(vec![actions::BasicSynthesis.into()])
+		.run();
+	assert_eq!(same.first_quality_difference, None);
+	assert_eq!(same.difference().quality, 0);
+
+	// steps skipped after the craft ended don't count
+	let finished = sim
+		.compare()
+		.baseline(vec![actions::CarefulSynthesis.into(); 5])
+		.candidate(vec![actions::CarefulSynthesis.into(); 8])
+		.run();
+	assert!(finished.baseline.success);
+	assert_eq!(finished.baseline.steps, finished.candidate.steps);
+	assert_eq!(finished.difference().steps, 0);
+	assert_eq!(finished.difference().macro_duration, 0);
+
+	Ok(())
+}
+
 fn generate_recipe_lvl(
 	id: u32,
 	lvl: u8,
@@ -1254,6 +3743,7 @@ fn generate_recipe_lvl(
 	)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn generate_recipe_rlvl(
 	id: u32,
 	lvl: u8,
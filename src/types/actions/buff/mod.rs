@@ -1,3 +1,5 @@
+use crate::types::{enums::Buff, traits::BuffAction};
+
 // level 15
 mod veneration;
 pub use veneration::*;
@@ -20,6 +22,10 @@ pub use final_appraisal::*;
 mod waste_not_ii;
 pub use waste_not_ii::*;
 
+// level 50
+mod reclaim;
+pub use reclaim::*;
+
 // level 65
 mod manipulation;
 pub use manipulation::*;
@@ -31,3 +37,22 @@ pub use heart_and_soul::*;
 // level 96
 mod quick_innovation;
 pub use quick_innovation::*;
+
+/// The action that applies `buff` along with its tick and expiry callbacks, or `None` for
+/// buffs added directly by other actions, which have no callbacks.
+pub fn buff_action(buff: Buff) -> Option<&'static dyn BuffAction> {
+	// no catch-all arm, so that a new buff has to be mapped here
+	match buff {
+		Buff::WasteNot => Some(&WasteNot),
+		Buff::WasteNotII => Some(&WasteNotII),
+		Buff::Manipulation => Some(&Manipulation),
+		Buff::GreatStrides => Some(&GreatStrides),
+		Buff::Innovation => Some(&Innovation),
+		Buff::Veneration => Some(&Veneration),
+		Buff::FinalAppraisal => Some(&FinalAppraisal),
+		Buff::HeartAndSoul => Some(&HeartAndSoul),
+		Buff::TrainedPerfection => Some(&super::TrainedPerfection),
+		Buff::Reclaim => Some(&Reclaim),
+		Buff::InnerQuiet | Buff::MakersMark | Buff::MuscleMemory | Buff::Expedience => None,
+	}
+}
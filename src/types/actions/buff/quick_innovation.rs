@@ -35,6 +35,10 @@ impl CraftingAction for QuickInnovation {
 		(CraftingJob::Any, CraftingLevel::unchecked_new(26))
 	}
 
+	fn is_available_in(&self, game_version: GameVersion) -> bool {
+		game_version >= GameVersion::Dawntrail
+	}
+
 	fn get_type(&self) -> ActionType {
 		ActionType::Buff
 	}
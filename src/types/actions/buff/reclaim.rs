@@ -0,0 +1,68 @@
+use crate::types::{
+	enums::*,
+	structs::CraftingLevel,
+	traits::{BuffAction, CraftingAction},
+	Simulation,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Reclaim;
+
+impl BuffAction for Reclaim {
+	fn get_duration(&self, _simulation_state: &Simulation) -> i32 {
+		// lasts for the rest of the craft
+		i32::MAX
+	}
+
+	fn can_be_clipped(&self) -> bool {
+		false
+	}
+
+	fn get_buff(&self) -> Buff {
+		Buff::Reclaim
+	}
+
+	fn get_initial_stacks(&self) -> u32 {
+		0
+	}
+}
+
+impl CraftingAction for Reclaim {
+	fn skip_on_fail(&self) -> bool {
+		true
+	}
+
+	fn get_level_requirement(&self) -> (CraftingJob, CraftingLevel) {
+		(CraftingJob::Any, CraftingLevel::unchecked_new(50))
+	}
+
+	fn get_type(&self) -> ActionType {
+		ActionType::Buff
+	}
+
+	fn _get_success_rate(&self, _simulation_state: &Simulation) -> u32 {
+		100
+	}
+
+	fn _can_be_used(&self, simulation_state: &Simulation, _linear: Option<bool>) -> bool {
+		// only once per craft
+		!simulation_state.has_buff(self.get_buff())
+	}
+
+	fn get_base_cp_cost(&self, _simulation_state: &Simulation) -> u32 {
+		55
+	}
+
+	fn get_durability_cost(&self, _simulation_state: &Simulation) -> u32 {
+		0
+	}
+
+	fn execute_with_flags(
+		&self,
+		simulation_state: &mut Simulation,
+		_safe: bool,
+		_skip_stack_addition: bool,
+	) {
+		simulation_state.add_buff(self.get_applied_buff(simulation_state));
+	}
+}
@@ -1,5 +1,5 @@
 use crate::types::{
-	enums::{ActionType, Buff, CraftingJob, StepState},
+	enums::{ActionType, Buff, CrafterTrait, CraftingJob, StepState},
 	structs::CraftingLevel,
 	traits::{CraftingAction, GeneralAction},
 	Simulation,
@@ -8,6 +8,17 @@ use crate::types::{
 #[derive(Clone, Copy, PartialEq)]
 pub struct DelicateSynthesis;
 
+impl DelicateSynthesis {
+	/// The potency of the progress part, as [`GeneralAction::get_potency`] is the quality one.
+	pub fn get_progress_potency(&self, simulation_state: &Simulation) -> u32 {
+		if simulation_state.has_trait(CrafterTrait::DelicateSynthesisMastery) {
+			150
+		} else {
+			100
+		}
+	}
+}
+
 impl GeneralAction for DelicateSynthesis {
 	fn get_potency(&self, _simulation_state: &Simulation) -> u32 {
 		100
@@ -63,7 +74,7 @@ impl CraftingAction for DelicateSynthesis {
 	) {
 		// progress
 		let progression_increase = self.get_base_progression(simulation_state);
-		let progress_potency = self.get_potency(simulation_state);
+		let progress_potency = self.get_progress_potency(simulation_state);
 		let mut progress_buff_mod = self.get_base_bonus(simulation_state);
 		let mut progress_condition_mod = self.get_base_condition(simulation_state);
 
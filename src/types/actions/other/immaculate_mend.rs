@@ -1,5 +1,5 @@
 use crate::types::{
-	enums::{ActionType, CraftingJob},
+	enums::{ActionType, CraftingJob, GameVersion},
 	structs::CraftingLevel,
 	traits::CraftingAction,
 	Simulation,
@@ -13,6 +13,10 @@ impl CraftingAction for ImmaculateMend {
 		(CraftingJob::Any, CraftingLevel::unchecked_new(98))
 	}
 
+	fn is_available_in(&self, game_version: GameVersion) -> bool {
+		game_version >= GameVersion::Dawntrail
+	}
+
 	fn get_type(&self) -> ActionType {
 		ActionType::Repair
 	}
@@ -1,6 +1,6 @@
 use crate::types::{
 	actions,
-	enums::{ActionType, Buff, CraftingJob},
+	enums::{ActionType, Buff, CraftingJob, GameVersion},
 	structs::CraftingLevel,
 	traits::{BuffAction, CraftingAction},
 	Simulation,
@@ -34,6 +34,10 @@ impl CraftingAction for TrainedPerfection {
 		(CraftingJob::Any, CraftingLevel::unchecked_new(100))
 	}
 
+	fn is_available_in(&self, game_version: GameVersion) -> bool {
+		game_version >= GameVersion::Dawntrail
+	}
+
 	fn get_type(&self) -> ActionType {
 		ActionType::Buff
 	}
@@ -7,7 +7,7 @@ impl ProgressAction for BasicSynthesis {}
 
 impl GeneralAction for BasicSynthesis {
 	fn get_potency(&self, simulation_state: &Simulation) -> u32 {
-		if simulation_state.crafter_stats.level >= 31 {
+		if simulation_state.has_trait(CrafterTrait::BasicSynthesisMastery) {
 			120
 		} else {
 			100
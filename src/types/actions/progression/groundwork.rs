@@ -1,5 +1,5 @@
 use crate::types::{
-	enums::{ActionType, Buff, CraftingJob, StepState},
+	enums::{ActionType, Buff, CrafterTrait, CraftingJob, StepState},
 	structs::CraftingLevel,
 	traits::{CraftingAction, GeneralAction, ProgressAction},
 	Simulation,
@@ -82,7 +82,7 @@ impl CraftingAction for Groundwork {
 
 impl GeneralAction for Groundwork {
 	fn get_potency(&self, simulation_state: &Simulation) -> u32 {
-		let base_potency = if simulation_state.crafter_stats.level >= 86 {
+		let base_potency = if simulation_state.has_trait(CrafterTrait::GroundworkMastery) {
 			360
 		} else {
 			300
@@ -1,5 +1,5 @@
 use crate::types::{
-	enums::{ActionType, Buff, CraftingJob, StepState},
+	enums::{ActionType, Buff, CrafterTrait, CraftingJob, StepState},
 	structs::CraftingLevel,
 	traits::{CraftingAction, GeneralAction, ProgressAction},
 	Simulation,
@@ -82,7 +82,7 @@ impl CraftingAction for RapidSynthesis {
 
 impl GeneralAction for RapidSynthesis {
 	fn get_potency(&self, simulation_state: &Simulation) -> u32 {
-		if simulation_state.crafter_stats.level >= 63 {
+		if simulation_state.has_trait(CrafterTrait::RapidSynthesisMastery) {
 			500
 		} else {
 			250
@@ -1,6 +1,6 @@
 use crate::types::{
 	actions,
-	enums::{ActionType, Buff, CraftingJob, StepState},
+	enums::{ActionType, Buff, CrafterTrait, CraftingJob, GameVersion, StepState},
 	structs::CraftingLevel,
 	traits::{CraftingAction, GeneralAction, QualityAction},
 	Simulation,
@@ -15,7 +15,8 @@ impl CraftingAction for AdvancedTouch {
 	fn has_combo(&self, simulation_state: &Simulation) -> bool {
 		// need to check not only for StandardTouch, but that it was also combo'd
 		for step in simulation_state.steps.iter().rev() {
-			if step.action == actions::Observe.into()
+			if (step.action == actions::Observe.into()
+				&& simulation_state.game_version >= GameVersion::Dawntrail)
 				|| (step.action == actions::StandardTouch.into()
 					&& step.success.is_some_and(|x| x)
 					&& step.combo.is_some_and(|x| x))
@@ -107,7 +108,7 @@ impl CraftingAction for AdvancedTouch {
 		let efficiency = ((potency * buff_mod) as f32) as f64;
 		simulation_state.quality += (quality_increase * condition_mod * efficiency / 100.0) as u32;
 
-		if !skip_stack_addition && simulation_state.crafter_stats.level >= 11 {
+		if !skip_stack_addition && simulation_state.has_trait(CrafterTrait::InnerQuiet) {
 			simulation_state.add_inner_quiet_stacks(1);
 		}
 	}
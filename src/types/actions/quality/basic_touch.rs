@@ -80,7 +80,7 @@ impl CraftingAction for BasicTouch {
 		let efficiency = ((potency * buff_mod) as f32) as f64;
 		simulation_state.quality += (quality_increase * condition_mod * efficiency / 100.0) as u32;
 
-		if !skip_stack_addition && simulation_state.crafter_stats.level >= 11 {
+		if !skip_stack_addition && simulation_state.has_trait(CrafterTrait::InnerQuiet) {
 			simulation_state.add_inner_quiet_stacks(1);
 		}
 	}
@@ -1,5 +1,5 @@
 use crate::types::{
-	enums::{ActionType, Buff, CraftingJob, FailCause, StepState},
+	enums::{ActionType, Buff, CrafterTrait, CraftingJob, FailCause, StepState},
 	structs::CraftingLevel,
 	traits::{CraftingAction, GeneralAction, QualityAction},
 	Simulation,
@@ -122,7 +122,7 @@ impl CraftingAction for ByregotsBlessing {
 		let efficiency = ((potency * buff_mod) as f32) as f64;
 		simulation_state.quality += (quality_increase * condition_mod * efficiency / 100.0) as u32;
 
-		if !skip_stack_addition && simulation_state.crafter_stats.level >= 11 {
+		if !skip_stack_addition && simulation_state.has_trait(CrafterTrait::InnerQuiet) {
 			simulation_state.add_inner_quiet_stacks(1);
 		}
 
@@ -1,5 +1,5 @@
 use crate::types::{
-	enums::{ActionType, Buff, CraftingJob, StepState},
+	enums::{ActionType, Buff, CrafterTrait, CraftingJob, GameVersion, StepState},
 	structs::CraftingLevel,
 	traits::{CraftingAction, GeneralAction, QualityAction},
 	Simulation,
@@ -19,6 +19,10 @@ impl CraftingAction for DaringTouch {
 		(CraftingJob::Any, CraftingLevel::unchecked_new(96))
 	}
 
+	fn is_available_in(&self, game_version: GameVersion) -> bool {
+		game_version >= GameVersion::Dawntrail
+	}
+
 	fn get_type(&self) -> ActionType {
 		ActionType::Quality
 	}
@@ -89,7 +93,7 @@ impl CraftingAction for DaringTouch {
 		let efficiency = ((potency * buff_mod) as f32) as f64;
 		simulation_state.quality += (quality_increase * condition_mod * efficiency / 100.0) as u32;
 
-		if !skip_stack_addition && simulation_state.crafter_stats.level >= 11 {
+		if !skip_stack_addition && simulation_state.has_trait(CrafterTrait::InnerQuiet) {
 			simulation_state.add_inner_quiet_stacks(1);
 		}
 	}
@@ -1,5 +1,5 @@
 use crate::types::{
-	enums::{ActionType, Buff, CraftingJob, StepState},
+	enums::{ActionType, Buff, CrafterTrait, CraftingJob, StepState},
 	structs::{CraftingLevel, EffectiveBuff},
 	traits::{CraftingAction, GeneralAction, QualityAction},
 	Simulation,
@@ -85,11 +85,11 @@ impl CraftingAction for HastyTouch {
 		let efficiency = ((potency * buff_mod) as f32) as f64;
 		simulation_state.quality += (quality_increase * condition_mod * efficiency / 100.0) as u32;
 
-		if !skip_stack_addition && simulation_state.crafter_stats.level >= 11 {
+		if !skip_stack_addition && simulation_state.has_trait(CrafterTrait::InnerQuiet) {
 			simulation_state.add_inner_quiet_stacks(1);
 		}
 
-		if simulation_state.crafter_stats.level >= 96 {
+		if simulation_state.has_trait(CrafterTrait::HastyTouchMastery) {
 			simulation_state.add_buff(EffectiveBuff {
 				duration: 1,
 				stacks: 1,
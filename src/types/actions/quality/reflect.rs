@@ -1,5 +1,5 @@
 use crate::types::{
-	enums::{ActionType, Buff, CraftingJob, StepState},
+	enums::{ActionType, Buff, CrafterTrait, CraftingJob, GameVersion, StepState},
 	structs::CraftingLevel,
 	traits::{CraftingAction, GeneralAction, QualityAction},
 	Simulation,
@@ -96,7 +96,7 @@ impl CraftingAction for Reflect {
 		let efficiency = ((potency * buff_mod) as f32) as f64;
 		simulation_state.quality += (quality_increase * condition_mod * efficiency / 100.0) as u32;
 
-		if !skip_stack_addition && simulation_state.crafter_stats.level >= 11 {
+		if !skip_stack_addition && simulation_state.has_trait(CrafterTrait::InnerQuiet) {
 			simulation_state.add_inner_quiet_stacks(1);
 		}
 
@@ -107,8 +107,11 @@ impl CraftingAction for Reflect {
 }
 
 impl GeneralAction for Reflect {
-	fn get_potency(&self, _simulation_state: &Simulation) -> u32 {
-		300
+	fn get_potency(&self, simulation_state: &Simulation) -> u32 {
+		match simulation_state.game_version {
+			GameVersion::Endwalker => 100,
+			GameVersion::Dawntrail => 300,
+		}
 	}
 
 	fn get_base_durability_cost(&self, _simulation_state: &Simulation) -> u32 {
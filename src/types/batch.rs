@@ -0,0 +1,236 @@
+use bon::bon;
+use rand::random;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, num::NonZeroUsize, thread};
+
+use crate::types::{enums::FailCause, structs::SimulationResult, Simulation};
+
+/// Aggregate statistics over many randomized runs of the same rotation.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct BatchResult {
+	pub runs: usize,
+	pub successes: usize,
+	/// Number of runs that ended at each HQ%, indexed by HQ% (0 to 100).
+	pub hq_percent_distribution: Vec<usize>,
+	pub quality: Distribution,
+	pub progression: Distribution,
+	pub fail_causes: HashMap<FailCause, usize>,
+	/// Number of failed runs without a fail cause, i.e. the rotation ran out of actions, or
+	/// the policy out of steps, before progress was complete.
+	pub unfinished: usize,
+	/// Mean number of steps taken before the craft ended, not counting skipped trailing steps.
+	pub average_final_step: f64,
+}
+impl BatchResult {
+	pub fn success_rate(&self) -> f64 {
+		if self.runs == 0 {
+			0.0
+		} else {
+			self.successes as f64 / self.runs as f64
+		}
+	}
+
+	/// Share of runs that reached at least `hq_percent`.
+	pub fn hq_rate(&self, hq_percent: u32) -> f64 {
+		if self.runs == 0 {
+			return 0.0;
+		}
+		let reached: usize = self
+			.hq_percent_distribution
+			.iter()
+			.skip(hq_percent as usize)
+			.sum();
+		reached as f64 / self.runs as f64
+	}
+
+	fn from_summaries(summaries: Vec<RunSummary>) -> Self {
+		let runs = summaries.len();
+		let mut hq_percent_distribution = vec![0; 101];
+		let mut fail_causes = HashMap::new();
+		let mut successes = 0;
+		let mut unfinished = 0;
+		let mut total_steps = 0;
+		let mut quality = Vec::with_capacity(runs);
+		let mut progression = Vec::with_capacity(runs);
+
+		for summary in summaries {
+			if summary.success {
+				successes += 1;
+			} else if let Some(fail_cause) = summary.fail_cause {
+				*fail_causes.entry(fail_cause).or_insert(0) += 1;
+			} else {
+				unfinished += 1;
+			}
+			hq_percent_distribution[summary.hq_percent.min(100) as usize] += 1;
+			total_steps += summary.final_step;
+			quality.push(summary.quality);
+			progression.push(summary.progression);
+		}
+
+		Self {
+			runs,
+			successes,
+			hq_percent_distribution,
+			quality: Distribution::new(quality),
+			progression: Distribution::new(progression),
+			fail_causes,
+			unfinished,
+			average_final_step: if runs == 0 {
+				0.0
+			} else {
+				total_steps as f64 / runs as f64
+			},
+		}
+	}
+}
+
+// the parts of a `SimulationResult` a batch keeps, so that large batches stay small in memory
+struct RunSummary {
+	success: bool,
+	fail_cause: Option<FailCause>,
+	hq_percent: u32,
+	final_step: usize,
+	quality: u32,
+	progression: u32,
+}
+impl From<SimulationResult> for RunSummary {
+	fn from(result: SimulationResult) -> Self {
+		Self {
+			success: result.success,
+			fail_cause: result.fail_cause,
+			hq_percent: result.hq_percent,
+			final_step: result
+				.steps
+				.iter()
+				.rposition(|step| !step.skipped)
+				.map_or(0, |ix| ix + 1),
+			quality: result.simulation.quality,
+			progression: result.simulation.progression,
+		}
+	}
+}
+
+/// The sorted samples of a value across a batch of runs.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Distribution {
+	samples: Vec<u32>,
+}
+impl Distribution {
+	fn new(mut samples: Vec<u32>) -> Self {
+		samples.sort_unstable();
+		Self { samples }
+	}
+
+	pub fn samples(&self) -> &[u32] {
+		&self.samples
+	}
+
+	pub fn mean(&self) -> f64 {
+		if self.samples.is_empty() {
+			0.0
+		} else {
+			self.samples.iter().map(|&x| x as f64).sum::<f64>() / self.samples.len() as f64
+		}
+	}
+
+	pub fn min(&self) -> Option<u32> {
+		self.samples.first().copied()
+	}
+
+	pub fn max(&self) -> Option<u32> {
+		self.samples.last().copied()
+	}
+
+	/// The nearest-rank percentile, with `percentile` between 0 and 100.
+	pub fn percentile(&self, percentile: f64) -> Option<u32> {
+		if self.samples.is_empty() {
+			return None;
+		}
+		let rank = (percentile.clamp(0.0, 100.0) / 100.0 * self.samples.len() as f64).ceil();
+		let ix = (rank as usize)
+			.saturating_sub(1)
+			.min(self.samples.len() - 1);
+		Some(self.samples[ix])
+	}
+
+	pub fn median(&self) -> Option<u32> {
+		self.percentile(50.0)
+	}
+}
+
+#[bon]
+impl Simulation {
+	/// Runs this simulation's rotation `runs` times with random conditions and success rolls.
+	///
+	/// Run `i` is seeded with `seed + i`, so a seeded batch gives the same result no matter
+	/// how many threads it is spread over.
+	#[builder(finish_fn = run)]
+	pub fn batch(
+		&self,
+		runs: usize,
+		seed: Option<u64>,
+		/// Defaults to the available parallelism of the machine.
+		threads: Option<usize>,
+		safe: Option<bool>,
+	) -> BatchResult {
+		let safe = safe.unwrap_or(false);
+		self.run_batch(runs, seed, threads, |sim| {
+			sim.start().safe(safe).random_conditions(true).run()
+		})
+	}
+}
+
+impl Simulation {
+	// runs `run` on `runs` reseeded copies of this simulation, spread over `threads`
+	pub(crate) fn run_batch<F>(
+		&self,
+		runs: usize,
+		seed: Option<u64>,
+		threads: Option<usize>,
+		run: F,
+	) -> BatchResult
+	where
+		F: Fn(Simulation) -> SimulationResult + Sync,
+	{
+		let seed = seed.unwrap_or_else(random);
+		let threads = threads
+			.or_else(|| thread::available_parallelism().ok().map(NonZeroUsize::get))
+			.unwrap_or(1)
+			.clamp(1, runs.max(1));
+
+		let run_range = |template: Simulation, range: std::ops::Range<usize>| {
+			range
+				.map(|i| {
+					let mut sim = template.clone();
+					sim.reseed(seed.wrapping_add(i as u64));
+					RunSummary::from(run(sim))
+				})
+				.collect::<Vec<_>>()
+		};
+
+		let summaries = if threads == 1 {
+			run_range(self.clone(), 0..runs)
+		} else {
+			let chunk_size = (runs + threads - 1) / threads;
+			thread::scope(|scope| {
+				let handles: Vec<_> = (0..runs)
+					.step_by(chunk_size)
+					.map(|start| {
+						let template = self.clone();
+						let end = (start + chunk_size).min(runs);
+						scope.spawn(move || run_range(template, start..end))
+					})
+					.collect();
+				handles
+					.into_iter()
+					.flat_map(|handle| handle.join().expect("batch worker thread panicked"))
+					.collect()
+			})
+		};
+
+		BatchResult::from_summaries(summaries)
+	}
+}
@@ -0,0 +1,330 @@
+use bon::bon;
+use std::fmt;
+
+use crate::types::{
+	enums::CraftingActionEnum,
+	structs::{ActionResult, SimulationResult},
+	traits::CraftingAction,
+	Simulation,
+};
+
+/// Two rotations run on the same recipe and stats, with their steps aligned.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RotationComparison {
+	pub baseline: RotationSummary,
+	pub candidate: RotationSummary,
+	/// The steps of both rotations, with the actions they have in common on the same row.
+	pub steps: Vec<AlignedStep>,
+	/// The first row of `steps` after which the two rotations have different quality, or
+	/// `None` if they always have the same.
+	pub first_quality_difference: Option<usize>,
+}
+
+/// The outcome of one of the compared rotations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RotationSummary {
+	pub success: bool,
+	pub quality: u32,
+	pub hq_percent: u32,
+	pub cp_left: u32,
+	pub durability_left: i32,
+	/// The number of steps until the craft ended, not counting skipped trailing steps, as
+	/// in [`BatchResult::average_final_step`](crate::types::BatchResult::average_final_step).
+	pub steps: usize,
+	/// The time the macro takes until the craft ended, in seconds.
+	pub macro_duration: u32,
+}
+impl RotationSummary {
+	fn new(result: &SimulationResult) -> Self {
+		let simulation = &result.simulation;
+		let steps = result
+			.steps
+			.iter()
+			.rposition(|step| !step.skipped)
+			.map_or(0, |ix| ix + 1);
+		Self {
+			success: result.success,
+			quality: simulation.quality,
+			hq_percent: result.hq_percent,
+			cp_left: simulation.available_cp,
+			durability_left: simulation.durability,
+			steps,
+			macro_duration: result.steps[..steps]
+				.iter()
+				.map(|step| step.action.get_wait_duration())
+				.sum(),
+		}
+	}
+}
+
+/// How much the candidate rotation gains over the baseline, negative where it loses.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RotationDifference {
+	pub quality: i64,
+	pub hq_percent: i64,
+	pub cp_left: i64,
+	pub durability_left: i64,
+	pub steps: i64,
+	pub macro_duration: i64,
+}
+
+/// A row of the aligned rotations. Both sides are set when the rotations share the action.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlignedStep {
+	pub baseline: Option<ComparedStep>,
+	pub candidate: Option<ComparedStep>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComparedStep {
+	/// The index of the step in its rotation.
+	pub index: usize,
+	pub action: CraftingActionEnum,
+	/// The quality of the item after the step.
+	pub quality: u32,
+}
+
+impl RotationComparison {
+	pub fn new(baseline: &SimulationResult, candidate: &SimulationResult) -> Self {
+		let baseline_steps = compared_steps(baseline);
+		let candidate_steps = compared_steps(candidate);
+		let steps = align(&baseline_steps, &candidate_steps);
+
+		let mut baseline_quality = starting_quality(baseline);
+		let mut candidate_quality = starting_quality(candidate);
+		let first_quality_difference = steps.iter().position(|row| {
+			if let Some(step) = &row.baseline {
+				baseline_quality = step.quality;
+			}
+			if let Some(step) = &row.candidate {
+				candidate_quality = step.quality;
+			}
+			baseline_quality != candidate_quality
+		});
+
+		Self {
+			baseline: RotationSummary::new(baseline),
+			candidate: RotationSummary::new(candidate),
+			steps,
+			first_quality_difference,
+		}
+	}
+
+	pub fn difference(&self) -> RotationDifference {
+		let (baseline, candidate) = (&self.baseline, &self.candidate);
+		RotationDifference {
+			quality: candidate.quality as i64 - baseline.quality as i64,
+			hq_percent: candidate.hq_percent as i64 - baseline.hq_percent as i64,
+			cp_left: candidate.cp_left as i64 - baseline.cp_left as i64,
+			durability_left: candidate.durability_left as i64 - baseline.durability_left as i64,
+			steps: candidate.steps as i64 - baseline.steps as i64,
+			macro_duration: candidate.macro_duration as i64 - baseline.macro_duration as i64,
+		}
+	}
+}
+
+// the quality added by `step`, including buff ticks
+fn added_quality(step: &ActionResult) -> u32 {
+	step.added_quality + step.after_buff_tick.as_ref().map_or(0, |t| t.added_quality)
+}
+
+fn starting_quality(result: &SimulationResult) -> u32 {
+	result.simulation.quality - result.steps.iter().map(added_quality).sum::<u32>()
+}
+
+fn compared_steps(result: &SimulationResult) -> Vec<ComparedStep> {
+	let mut quality = starting_quality(result);
+	result
+		.steps
+		.iter()
+		.enumerate()
+		.map(|(index, step)| {
+			quality += added_quality(step);
+			ComparedStep {
+				index,
+				action: step.action,
+				quality,
+			}
+		})
+		.collect()
+}
+
+// lines up the longest common sequence of actions, keeping the steps of each rotation in
+// order and putting removed steps before added ones
+fn align(baseline: &[ComparedStep], candidate: &[ComparedStep]) -> Vec<AlignedStep> {
+	// common[i][j] is the length of the longest common sequence of baseline[i..] and
+	// candidate[j..]
+	let mut common = vec![vec![0usize; candidate.len() + 1]; baseline.len() + 1];
+	for i in (0..baseline.len()).rev() {
+		for j in (0..candidate.len()).rev() {
+			common[i][j] = if baseline[i].action == candidate[j].action {
+				common[i + 1][j + 1] + 1
+			} else {
+				common[i + 1][j].max(common[i][j + 1])
+			};
+		}
+	}
+
+	let (mut i, mut j) = (0, 0);
+	let mut rows = vec![];
+	while i < baseline.len() || j < candidate.len() {
+		let row = if i < baseline.len()
+			&& j < candidate.len()
+			&& baseline[i].action == candidate[j].action
+		{
+			i += 1;
+			j += 1;
+			AlignedStep {
+				baseline: Some(baseline[i - 1].clone()),
+				candidate: Some(candidate[j - 1].clone()),
+			}
+		} else if j == candidate.len()
+			|| (i < baseline.len() && common[i + 1][j] >= common[i][j + 1])
+		{
+			i += 1;
+			AlignedStep {
+				baseline: Some(baseline[i - 1].clone()),
+				candidate: None,
+			}
+		} else {
+			j += 1;
+			AlignedStep {
+				baseline: None,
+				candidate: Some(candidate[j - 1].clone()),
+			}
+		};
+		rows.push(row);
+	}
+	rows
+}
+
+impl fmt::Display for RotationComparison {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let (baseline, candidate) = (&self.baseline, &self.candidate);
+		let difference = self.difference();
+		writeln!(
+			f,
+			"{:<16} {:>10} {:>10} {:>10}",
+			"", "baseline", "candidate", "difference"
+		)?;
+		let rows = [
+			(
+				"success",
+				baseline.success.to_string(),
+				candidate.success.to_string(),
+				String::new(),
+			),
+			(
+				"quality",
+				baseline.quality.to_string(),
+				candidate.quality.to_string(),
+				format!("{:+}", difference.quality),
+			),
+			(
+				"HQ%",
+				baseline.hq_percent.to_string(),
+				candidate.hq_percent.to_string(),
+				format!("{:+}", difference.hq_percent),
+			),
+			(
+				"CP left",
+				baseline.cp_left.to_string(),
+				candidate.cp_left.to_string(),
+				format!("{:+}", difference.cp_left),
+			),
+			(
+				"durability left",
+				baseline.durability_left.to_string(),
+				candidate.durability_left.to_string(),
+				format!("{:+}", difference.durability_left),
+			),
+			(
+				"steps",
+				baseline.steps.to_string(),
+				candidate.steps.to_string(),
+				format!("{:+}", difference.steps),
+			),
+			(
+				"macro time (s)",
+				baseline.macro_duration.to_string(),
+				candidate.macro_duration.to_string(),
+				format!("{:+}", difference.macro_duration),
+			),
+		];
+		for (name, baseline, candidate, difference) in rows {
+			writeln!(
+				f,
+				"{:<16} {:>10} {:>10} {:>10}",
+				name, baseline, candidate, difference
+			)?;
+		}
+
+		writeln!(f)?;
+		for (row_ix, row) in self.steps.iter().enumerate() {
+			let marker = match (&row.baseline, &row.candidate) {
+				_ if Some(row_ix) == self.first_quality_difference => '>',
+				(Some(_), None) => '-',
+				(None, Some(_)) => '+',
+				_ => ' ',
+			};
+			write!(f, "{} ", marker)?;
+			write_step(f, row.baseline.as_ref())?;
+			write!(f, " | ")?;
+			write_step(f, row.candidate.as_ref())?;
+			writeln!(f)?;
+		}
+		let Some(row) = self.first_quality_difference else {
+			return write!(f, "quality never differs");
+		};
+		let row = &self.steps[row];
+		let sides: Vec<_> = [("baseline", &row.baseline), ("candidate", &row.candidate)]
+			.into_iter()
+			.filter_map(|(name, step)| {
+				let step = step.as_ref()?;
+				Some(format!(
+					"step {} of the {} ({})",
+					step.index + 1,
+					name,
+					step.quality
+				))
+			})
+			.collect();
+		write!(f, "> quality first differs after {}", sides.join(" and "))
+	}
+}
+
+fn write_step(f: &mut fmt::Formatter<'_>, step: Option<&ComparedStep>) -> fmt::Result {
+	match step {
+		Some(step) => write!(
+			f,
+			"{:>3} {:<22} {:>6}",
+			step.index + 1,
+			step.action.game_name(),
+			step.quality
+		),
+		None => write!(f, "{:33}", ""),
+	}
+}
+
+#[bon]
+impl Simulation {
+	/// Runs `baseline` and `candidate` on copies of this simulation, and compares them.
+	///
+	/// Unless `linear` is false, both are run linearly: every action succeeds, and the
+	/// condition stays Normal except where `step_states` sets it.
+	#[builder(finish_fn = run)]
+	pub fn compare(
+		&self,
+		baseline: Vec<CraftingActionEnum>,
+		candidate: Vec<CraftingActionEnum>,
+		linear: Option<bool>,
+	) -> RotationComparison {
+		let linear = linear.unwrap_or(true);
+		let run = |actions| {
+			let mut simulation = self.clone();
+			simulation.actions = actions;
+			simulation.start().linear(linear).run()
+		};
+		RotationComparison::new(&run(baseline), &run(candidate))
+	}
+}
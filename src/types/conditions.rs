@@ -0,0 +1,162 @@
+use num_traits::FromPrimitive;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::types::{enums::StepState, structs::Craft};
+
+/// The conditions a recipe can have, as stored in the game's `ConditionsFlag`: bit `n` is set
+/// when the condition with discriminant `n + 1` of [`StepState`] is possible.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(from = "u32", into = "u32"))]
+pub struct ConditionSet {
+	flag: u32,
+}
+impl ConditionSet {
+	/// Normal, Good, Excellent and Poor, the conditions of every recipe that isn't expert.
+	pub const STANDARD: ConditionSet = ConditionSet { flag: 15 };
+
+	pub fn flag(&self) -> u32 {
+		self.flag
+	}
+
+	pub fn contains(&self, state: StepState) -> bool {
+		Self::bit(state).is_some_and(|bit| self.flag & bit != 0)
+	}
+
+	/// Adds `state`, unless it's [`StepState::None`], which isn't a condition.
+	pub fn insert(&mut self, state: StepState) {
+		if let Some(bit) = Self::bit(state) {
+			self.flag |= bit;
+		}
+	}
+
+	/// The conditions in the set, in the order of [`StepState`]. Bits that don't match any
+	/// condition are ignored.
+	pub fn iter(&self) -> impl Iterator<Item = StepState> + '_ {
+		(0..u32::BITS as usize)
+			.filter(|ix| self.flag & (1 << ix) != 0)
+			.filter_map(|ix| StepState::from_usize(ix + 1))
+	}
+
+	fn bit(state: StepState) -> Option<u32> {
+		(state != StepState::None).then(|| 1 << (state as u32 - 1))
+	}
+}
+impl From<u32> for ConditionSet {
+	fn from(flag: u32) -> Self {
+		Self { flag }
+	}
+}
+impl From<ConditionSet> for u32 {
+	fn from(value: ConditionSet) -> Self {
+		value.flag
+	}
+}
+impl FromIterator<StepState> for ConditionSet {
+	fn from_iter<T: IntoIterator<Item = StepState>>(iter: T) -> Self {
+		let mut set = ConditionSet::default();
+		for state in iter {
+			set.insert(state);
+		}
+		set
+	}
+}
+
+impl Craft {
+	pub fn conditions(&self) -> ConditionSet {
+		ConditionSet::from(self.conditions_flag)
+	}
+}
+
+/// The chance of each condition to follow a step, for one class of recipes. Conditions the
+/// recipe can't have are skipped, and Normal takes whatever chance is left.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct RecipeConditionRates {
+	pub good: f64,
+	/// The chance of Good once the crafter has the Quality Assurance trait.
+	pub good_with_quality_assurance: f64,
+	pub excellent: f64,
+	pub poor: f64,
+	pub centered: f64,
+	pub sturdy: f64,
+	pub pliant: f64,
+	pub malleable: f64,
+	pub primed: f64,
+	pub good_omen: f64,
+}
+impl RecipeConditionRates {
+	/// The chance of `state`, or `None` for Normal and [`StepState::None`].
+	pub fn rate(&self, state: StepState, quality_assurance: bool) -> Option<f64> {
+		match state {
+			StepState::Good if quality_assurance => Some(self.good_with_quality_assurance),
+			StepState::Good => Some(self.good),
+			StepState::Excellent => Some(self.excellent),
+			StepState::Poor => Some(self.poor),
+			StepState::Centered => Some(self.centered),
+			StepState::Sturdy => Some(self.sturdy),
+			StepState::Pliant => Some(self.pliant),
+			StepState::Malleable => Some(self.malleable),
+			StepState::Primed => Some(self.primed),
+			StepState::GoodOmen => Some(self.good_omen),
+			StepState::Normal | StepState::None => None,
+		}
+	}
+}
+
+/// The condition rates used by a simulation. Defaults to the rates of the current game
+/// version.
+///
+/// If the rates of a recipe add up past 1, they are scaled down to add up to exactly 1,
+/// leaving no chance of Normal.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ConditionRates {
+	pub standard: RecipeConditionRates,
+	pub expert: RecipeConditionRates,
+	/// The rates of each class of recipes, identified by the conditions they can have,
+	/// which replace `standard` and `expert` for those recipes. The game's own rates only
+	/// depend on whether a recipe is expert, so there are none by default.
+	#[cfg_attr(feature = "serde", serde(default))]
+	pub classes: Vec<(ConditionSet, RecipeConditionRates)>,
+}
+impl ConditionRates {
+	pub fn for_recipe(&self, recipe: &Craft) -> &RecipeConditionRates {
+		let conditions = recipe.conditions();
+		if let Some((_, rates)) = self.classes.iter().find(|(set, _)| *set == conditions) {
+			rates
+		} else if recipe.expert.is_some_and(|b| b) {
+			&self.expert
+		} else {
+			&self.standard
+		}
+	}
+}
+impl Default for ConditionRates {
+	fn default() -> Self {
+		// Poor only ever follows Excellent, which the simulation handles on its own
+		let standard = RecipeConditionRates {
+			good: 0.2,
+			good_with_quality_assurance: 0.25,
+			excellent: 0.04,
+			poor: 0.0,
+			centered: 0.15,
+			sturdy: 0.15,
+			pliant: 0.12,
+			malleable: 0.12,
+			primed: 0.12,
+			good_omen: 0.1,
+		};
+		Self {
+			standard,
+			expert: RecipeConditionRates {
+				good: 0.12,
+				good_with_quality_assurance: 0.12,
+				excellent: 0.0,
+				..standard
+			},
+			classes: vec![],
+		}
+	}
+}
@@ -0,0 +1,153 @@
+use bon::bon;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::types::{structs::CrafterStats, QualityTarget, Simulation};
+
+/// A percentage bonus to a stat, up to a flat maximum.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct StatBonus {
+	pub percent: u32,
+	pub max: u32,
+}
+impl StatBonus {
+	pub fn apply(&self, base: u32) -> u32 {
+		(base * self.percent / 100).min(self.max)
+	}
+}
+
+/// The bonuses of one quality of a consumable.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ConsumableBonuses {
+	pub craftsmanship: Option<StatBonus>,
+	pub control: Option<StatBonus>,
+	pub cp: Option<StatBonus>,
+}
+
+/// A food or medicine, with the bonuses of its NQ and HQ variants.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Consumable {
+	pub name: String,
+	pub nq: ConsumableBonuses,
+	pub hq: ConsumableBonuses,
+}
+impl Consumable {
+	pub fn bonuses(&self, hq: bool) -> &ConsumableBonuses {
+		if hq {
+			&self.hq
+		} else {
+			&self.nq
+		}
+	}
+}
+
+/// A consumable picked by [`Simulation::compare_consumables`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct SelectedConsumable {
+	pub name: String,
+	pub hq: bool,
+}
+
+/// The first food and medicine combination that reaches a target.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct ConsumablePlan {
+	pub food: Option<SelectedConsumable>,
+	pub medicine: Option<SelectedConsumable>,
+	/// The crafter's stats with the food and medicine applied.
+	pub crafter_stats: CrafterStats,
+	pub quality: u32,
+	pub hq_percent: u32,
+}
+
+impl CrafterStats {
+	/// The effective stats with the given food and medicine, as `(consumable, hq)`.
+	///
+	/// Each bonus is computed from these base stats, so food and medicine stack additively.
+	pub fn with_consumables(
+		&self,
+		food: Option<(&Consumable, bool)>,
+		medicine: Option<(&Consumable, bool)>,
+	) -> CrafterStats {
+		let mut stats = self.clone();
+		for bonuses in [food, medicine]
+			.into_iter()
+			.flatten()
+			.map(|(consumable, hq)| consumable.bonuses(hq))
+		{
+			let bonus = |stat: Option<StatBonus>, base| stat.map_or(0, |stat| stat.apply(base));
+			stats.craftsmanship += bonus(bonuses.craftsmanship, self.craftsmanship);
+			stats.control += bonus(bonuses.control, self.control);
+			stats.cp += bonus(bonuses.cp, self.cp);
+		}
+		stats
+	}
+}
+
+#[bon]
+impl Simulation {
+	/// Finds the first food and medicine combination with which this simulation's rotation
+	/// succeeds and, if given, reaches `target`, or `None` if no combination does.
+	///
+	/// Combinations are tried from the weakest: no consumable first, then NQ before HQ, and
+	/// consumables in the order they are given, trying combinations with fewer and earlier
+	/// picks before later ones. Each is checked by running the rotation in linear mode.
+	#[builder(finish_fn = run)]
+	pub fn compare_consumables(
+		&self,
+		foods: &[Consumable],
+		medicines: &[Consumable],
+		target: Option<QualityTarget>,
+	) -> Option<ConsumablePlan> {
+		let food_choices = slot_choices(foods);
+		let medicine_choices = slot_choices(medicines);
+
+		let mut combinations: Vec<_> = (0..food_choices.len())
+			.flat_map(|food| (0..medicine_choices.len()).map(move |medicine| (food, medicine)))
+			.collect();
+		combinations.sort_by_key(|&(food, medicine)| (food + medicine, food));
+
+		combinations.into_iter().find_map(|(food, medicine)| {
+			let food = food_choices[food];
+			let medicine = medicine_choices[medicine];
+			let crafter_stats = self.crafter_stats.with_consumables(food, medicine);
+			// a copy keeps the starting quality, step states and condition rates
+			let mut simulation = self.clone();
+			simulation.crafter_stats = crafter_stats.clone();
+			simulation.reset();
+			let result = simulation.start().linear(true).run();
+
+			let reached =
+				result.success && target.map_or(true, |target| target.is_reached_by(&result));
+			let selected = |choice: Option<(&Consumable, bool)>| {
+				choice.map(|(consumable, hq)| SelectedConsumable {
+					name: consumable.name.clone(),
+					hq,
+				})
+			};
+			reached.then(|| ConsumablePlan {
+				food: selected(food),
+				medicine: selected(medicine),
+				crafter_stats,
+				quality: result.simulation.quality,
+				hq_percent: result.hq_percent,
+			})
+		})
+	}
+}
+
+// every choice for one consumable slot, from the weakest: none, then each consumable in NQ
+// and HQ
+fn slot_choices(consumables: &[Consumable]) -> Vec<Option<(&Consumable, bool)>> {
+	let mut choices = vec![None];
+	choices.extend(
+		consumables
+			.iter()
+			.flat_map(|consumable| [Some((consumable, false)), Some((consumable, true))]),
+	);
+	choices
+}
@@ -1,9 +1,13 @@
 use enum_dispatch::enum_dispatch;
 use num_derive::FromPrimitive;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
 
 use crate::types::actions::*;
 
 #[derive(Clone, Copy, Debug, Eq, FromPrimitive, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub enum StepState {
 	None, // Fails the step
 	Normal,
@@ -44,6 +48,7 @@ pub enum CraftingActionEnum {
 	Groundwork,
 	MuscleMemory,
 	IntensiveSynthesis,
+	FocusedSynthesis,
 
 	// Quality actions
 	BasicTouch,
@@ -59,6 +64,7 @@ pub enum CraftingActionEnum {
 	TrainedFinesse,
 	DaringTouch,
 	RefinedTouch,
+	FocusedTouch,
 
 	// CP recovery
 	TricksOfTheTrade,
@@ -75,6 +81,7 @@ pub enum CraftingActionEnum {
 	Innovation,
 	Veneration,
 	FinalAppraisal,
+	Reclaim,
 	QuickInnovation,
 	TrainedPerfection,
 
@@ -85,8 +92,170 @@ pub enum CraftingActionEnum {
 	DelicateSynthesis,
 	RemoveFinalAppraisal,
 }
+impl CraftingActionEnum {
+	pub const ALL: [CraftingActionEnum; 40] = [
+		CraftingActionEnum::BasicSynthesis(BasicSynthesis),
+		CraftingActionEnum::CarefulSynthesis(CarefulSynthesis),
+		CraftingActionEnum::PrudentSynthesis(PrudentSynthesis),
+		CraftingActionEnum::RapidSynthesis(RapidSynthesis),
+		CraftingActionEnum::Groundwork(Groundwork),
+		CraftingActionEnum::MuscleMemory(MuscleMemory),
+		CraftingActionEnum::IntensiveSynthesis(IntensiveSynthesis),
+		CraftingActionEnum::FocusedSynthesis(FocusedSynthesis),
+		CraftingActionEnum::BasicTouch(BasicTouch),
+		CraftingActionEnum::StandardTouch(StandardTouch),
+		CraftingActionEnum::AdvancedTouch(AdvancedTouch),
+		CraftingActionEnum::HastyTouch(HastyTouch),
+		CraftingActionEnum::ByregotsBlessing(ByregotsBlessing),
+		CraftingActionEnum::PreciseTouch(PreciseTouch),
+		CraftingActionEnum::PrudentTouch(PrudentTouch),
+		CraftingActionEnum::TrainedEye(TrainedEye),
+		CraftingActionEnum::PreparatoryTouch(PreparatoryTouch),
+		CraftingActionEnum::Reflect(Reflect),
+		CraftingActionEnum::TrainedFinesse(TrainedFinesse),
+		CraftingActionEnum::DaringTouch(DaringTouch),
+		CraftingActionEnum::RefinedTouch(RefinedTouch),
+		CraftingActionEnum::FocusedTouch(FocusedTouch),
+		CraftingActionEnum::TricksOfTheTrade(TricksOfTheTrade),
+		CraftingActionEnum::MastersMend(MastersMend),
+		CraftingActionEnum::Manipulation(Manipulation),
+		CraftingActionEnum::ImmaculateMend(ImmaculateMend),
+		CraftingActionEnum::WasteNot(WasteNot),
+		CraftingActionEnum::WasteNotII(WasteNotII),
+		CraftingActionEnum::GreatStrides(GreatStrides),
+		CraftingActionEnum::Innovation(Innovation),
+		CraftingActionEnum::Veneration(Veneration),
+		CraftingActionEnum::FinalAppraisal(FinalAppraisal),
+		CraftingActionEnum::Reclaim(Reclaim),
+		CraftingActionEnum::QuickInnovation(QuickInnovation),
+		CraftingActionEnum::TrainedPerfection(TrainedPerfection),
+		CraftingActionEnum::Observe(Observe),
+		CraftingActionEnum::HeartAndSoul(HeartAndSoul),
+		CraftingActionEnum::CarefulObservation(CarefulObservation),
+		CraftingActionEnum::DelicateSynthesis(DelicateSynthesis),
+		CraftingActionEnum::RemoveFinalAppraisal(RemoveFinalAppraisal),
+	];
 
-#[derive(Clone, Copy, PartialEq)]
+	/// The stable identifier of this action, matching its variant name (e.g. `"BasicTouch"`).
+	pub fn name(&self) -> &'static str {
+		match self {
+			CraftingActionEnum::BasicSynthesis(_) => "BasicSynthesis",
+			CraftingActionEnum::CarefulSynthesis(_) => "CarefulSynthesis",
+			CraftingActionEnum::PrudentSynthesis(_) => "PrudentSynthesis",
+			CraftingActionEnum::RapidSynthesis(_) => "RapidSynthesis",
+			CraftingActionEnum::Groundwork(_) => "Groundwork",
+			CraftingActionEnum::MuscleMemory(_) => "MuscleMemory",
+			CraftingActionEnum::IntensiveSynthesis(_) => "IntensiveSynthesis",
+			CraftingActionEnum::FocusedSynthesis(_) => "FocusedSynthesis",
+			CraftingActionEnum::BasicTouch(_) => "BasicTouch",
+			CraftingActionEnum::StandardTouch(_) => "StandardTouch",
+			CraftingActionEnum::AdvancedTouch(_) => "AdvancedTouch",
+			CraftingActionEnum::HastyTouch(_) => "HastyTouch",
+			CraftingActionEnum::ByregotsBlessing(_) => "ByregotsBlessing",
+			CraftingActionEnum::PreciseTouch(_) => "PreciseTouch",
+			CraftingActionEnum::PrudentTouch(_) => "PrudentTouch",
+			CraftingActionEnum::TrainedEye(_) => "TrainedEye",
+			CraftingActionEnum::PreparatoryTouch(_) => "PreparatoryTouch",
+			CraftingActionEnum::Reflect(_) => "Reflect",
+			CraftingActionEnum::TrainedFinesse(_) => "TrainedFinesse",
+			CraftingActionEnum::DaringTouch(_) => "DaringTouch",
+			CraftingActionEnum::RefinedTouch(_) => "RefinedTouch",
+			CraftingActionEnum::FocusedTouch(_) => "FocusedTouch",
+			CraftingActionEnum::TricksOfTheTrade(_) => "TricksOfTheTrade",
+			CraftingActionEnum::MastersMend(_) => "MastersMend",
+			CraftingActionEnum::Manipulation(_) => "Manipulation",
+			CraftingActionEnum::ImmaculateMend(_) => "ImmaculateMend",
+			CraftingActionEnum::WasteNot(_) => "WasteNot",
+			CraftingActionEnum::WasteNotII(_) => "WasteNotII",
+			CraftingActionEnum::GreatStrides(_) => "GreatStrides",
+			CraftingActionEnum::Innovation(_) => "Innovation",
+			CraftingActionEnum::Veneration(_) => "Veneration",
+			CraftingActionEnum::FinalAppraisal(_) => "FinalAppraisal",
+			CraftingActionEnum::Reclaim(_) => "Reclaim",
+			CraftingActionEnum::QuickInnovation(_) => "QuickInnovation",
+			CraftingActionEnum::TrainedPerfection(_) => "TrainedPerfection",
+			CraftingActionEnum::Observe(_) => "Observe",
+			CraftingActionEnum::HeartAndSoul(_) => "HeartAndSoul",
+			CraftingActionEnum::CarefulObservation(_) => "CarefulObservation",
+			CraftingActionEnum::DelicateSynthesis(_) => "DelicateSynthesis",
+			CraftingActionEnum::RemoveFinalAppraisal(_) => "RemoveFinalAppraisal",
+		}
+	}
+
+	/// The name of this action as shown in game, used by `/ac` macro commands.
+	///
+	/// [`RemoveFinalAppraisal`] shares its name with the buff it removes.
+	pub fn game_name(&self) -> &'static str {
+		match self {
+			CraftingActionEnum::BasicSynthesis(_) => "Basic Synthesis",
+			CraftingActionEnum::CarefulSynthesis(_) => "Careful Synthesis",
+			CraftingActionEnum::PrudentSynthesis(_) => "Prudent Synthesis",
+			CraftingActionEnum::RapidSynthesis(_) => "Rapid Synthesis",
+			CraftingActionEnum::Groundwork(_) => "Groundwork",
+			CraftingActionEnum::MuscleMemory(_) => "Muscle Memory",
+			CraftingActionEnum::IntensiveSynthesis(_) => "Intensive Synthesis",
+			CraftingActionEnum::FocusedSynthesis(_) => "Focused Synthesis",
+			CraftingActionEnum::BasicTouch(_) => "Basic Touch",
+			CraftingActionEnum::StandardTouch(_) => "Standard Touch",
+			CraftingActionEnum::AdvancedTouch(_) => "Advanced Touch",
+			CraftingActionEnum::HastyTouch(_) => "Hasty Touch",
+			CraftingActionEnum::ByregotsBlessing(_) => "Byregot's Blessing",
+			CraftingActionEnum::PreciseTouch(_) => "Precise Touch",
+			CraftingActionEnum::PrudentTouch(_) => "Prudent Touch",
+			CraftingActionEnum::TrainedEye(_) => "Trained Eye",
+			CraftingActionEnum::PreparatoryTouch(_) => "Preparatory Touch",
+			CraftingActionEnum::Reflect(_) => "Reflect",
+			CraftingActionEnum::TrainedFinesse(_) => "Trained Finesse",
+			CraftingActionEnum::DaringTouch(_) => "Daring Touch",
+			CraftingActionEnum::RefinedTouch(_) => "Refined Touch",
+			CraftingActionEnum::FocusedTouch(_) => "Focused Touch",
+			CraftingActionEnum::TricksOfTheTrade(_) => "Tricks of the Trade",
+			CraftingActionEnum::MastersMend(_) => "Master's Mend",
+			CraftingActionEnum::Manipulation(_) => "Manipulation",
+			CraftingActionEnum::ImmaculateMend(_) => "Immaculate Mend",
+			CraftingActionEnum::WasteNot(_) => "Waste Not",
+			CraftingActionEnum::WasteNotII(_) => "Waste Not II",
+			CraftingActionEnum::GreatStrides(_) => "Great Strides",
+			CraftingActionEnum::Innovation(_) => "Innovation",
+			CraftingActionEnum::Veneration(_) => "Veneration",
+			CraftingActionEnum::FinalAppraisal(_) => "Final Appraisal",
+			CraftingActionEnum::Reclaim(_) => "Reclaim",
+			CraftingActionEnum::QuickInnovation(_) => "Quick Innovation",
+			CraftingActionEnum::TrainedPerfection(_) => "Trained Perfection",
+			CraftingActionEnum::Observe(_) => "Observe",
+			CraftingActionEnum::HeartAndSoul(_) => "Heart and Soul",
+			CraftingActionEnum::CarefulObservation(_) => "Careful Observation",
+			CraftingActionEnum::DelicateSynthesis(_) => "Delicate Synthesis",
+			CraftingActionEnum::RemoveFinalAppraisal(_) => "Final Appraisal",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<CraftingActionEnum> {
+		Self::ALL.into_iter().find(|action| action.name() == name)
+	}
+}
+impl fmt::Debug for CraftingActionEnum {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
+#[cfg(feature = "serde")]
+impl Serialize for CraftingActionEnum {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(self.name())
+	}
+}
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for CraftingActionEnum {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let name = String::deserialize(deserializer)?;
+		CraftingActionEnum::from_name(&name)
+			.ok_or_else(|| serde::de::Error::custom(format!("unknown crafting action `{}`", name)))
+	}
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub enum Buff {
 	InnerQuiet,
 
@@ -112,9 +281,77 @@ pub enum Buff {
 	Expedience,
 
 	TrainedPerfection,
+
+	Reclaim,
+}
+impl Buff {
+	pub const ALL: [Buff; 14] = [
+		Buff::InnerQuiet,
+		Buff::WasteNot,
+		Buff::WasteNotII,
+		Buff::Manipulation,
+		Buff::GreatStrides,
+		Buff::Innovation,
+		Buff::Veneration,
+		Buff::MakersMark,
+		Buff::MuscleMemory,
+		Buff::FinalAppraisal,
+		Buff::HeartAndSoul,
+		Buff::Expedience,
+		Buff::TrainedPerfection,
+		Buff::Reclaim,
+	];
 }
 
-#[derive(Clone, Copy, PartialEq)]
+/// Crafter traits that change how the simulation behaves, learned by level.
+///
+/// Durability restoration isn't level gated: Master's Mend, Manipulation and Immaculate Mend
+/// restore the same durability at every level, so no trait changes them.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum CrafterTrait {
+	/// Quality actions grant Inner Quiet stacks.
+	InnerQuiet,
+	/// Basic Synthesis potency goes from 100 to 120.
+	BasicSynthesisMastery,
+	/// Rapid Synthesis potency goes from 250 to 500.
+	RapidSynthesisMastery,
+	/// The chance of a Good condition goes from 20% to 25%.
+	QualityAssurance,
+	/// Careful Synthesis potency goes from 150 to 180.
+	CarefulSynthesisMastery,
+	/// Groundwork potency goes from 300 to 360.
+	GroundworkMastery,
+	/// Delicate Synthesis progress potency goes from 100 to 150.
+	DelicateSynthesisMastery,
+	/// Hasty Touch grants Expedience.
+	HastyTouchMastery,
+}
+
+/// A game version whose crafting rules can be simulated, in release order.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum GameVersion {
+	/// Patches 6.0 to 6.58: Focused Synthesis and Focused Touch follow Observe, and Reflect
+	/// has a potency of 100.
+	Endwalker,
+	/// Patch 7.0 onwards.
+	#[default]
+	Dawntrail,
+}
+impl GameVersion {
+	pub fn has_trait(&self, crafter_trait: CrafterTrait) -> bool {
+		match crafter_trait {
+			CrafterTrait::DelicateSynthesisMastery | CrafterTrait::HastyTouchMastery => {
+				*self >= GameVersion::Dawntrail
+			}
+			_ => true,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub enum CraftingJob {
 	Any,
 	Carpenter,
@@ -127,7 +364,8 @@ pub enum CraftingJob {
 	Culinarian,
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub enum FailCause {
 	UnsafeAction,
 	DurabilityReachedZero,
@@ -137,4 +375,6 @@ pub enum FailCause {
 	NotSpecialist,
 	NoInnerQuiet,
 	QualityTooLow,
+	/// The action doesn't exist in the simulated game version.
+	UnavailableInGameVersion,
 }
@@ -0,0 +1,188 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::types::{
+	enums::{Buff, StepState},
+	traits::CraftingAction,
+	Simulation,
+};
+
+/// One possible end of a craft, and how likely it is.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct Outcome {
+	pub probability: f64,
+	pub success: bool,
+	pub progression: u32,
+	pub quality: u32,
+	pub hq_percent: u32,
+}
+
+/// Every possible outcome of a rotation under random conditions and success rolls.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct OutcomeDistribution {
+	/// Distinct outcomes, sorted by ascending quality and progression.
+	pub outcomes: Vec<Outcome>,
+}
+impl OutcomeDistribution {
+	pub fn success_probability(&self) -> f64 {
+		self.outcomes
+			.iter()
+			.filter(|outcome| outcome.success)
+			.map(|outcome| outcome.probability)
+			.sum()
+	}
+
+	pub fn expected_quality(&self) -> f64 {
+		self.outcomes
+			.iter()
+			.map(|outcome| outcome.quality as f64 * outcome.probability)
+			.sum()
+	}
+
+	/// Probability of ending at each HQ%, indexed by HQ% (0 to 100).
+	pub fn hq_percent_distribution(&self) -> Vec<f64> {
+		let mut distribution = vec![0.0; 101];
+		self.outcomes.iter().for_each(|outcome| {
+			distribution[outcome.hq_percent.min(100) as usize] += outcome.probability
+		});
+		distribution
+	}
+}
+
+// everything about a simulation in progress that can influence the rest of the craft,
+// used to merge branches that reached the same state in different ways
+#[derive(Eq, Hash, PartialEq)]
+struct BranchKey {
+	progression: u32,
+	quality: u32,
+	durability: i32,
+	available_cp: u32,
+	state: StepState,
+	buffs: Vec<(Buff, i32, u32, u32)>,
+	// the outcome and combo of the trailing steps that combos look back on
+	combo_history: Vec<(Option<bool>, Option<bool>)>,
+}
+impl BranchKey {
+	fn new(simulation: &Simulation) -> Self {
+		let mut buffs: Vec<_> = simulation
+			.buffs()
+			.iter()
+			.map(|b| (b.buff, b.duration, b.stacks, b.applied_step))
+			.collect();
+		buffs.sort_by_key(|&(buff, ..)| buff as u8);
+
+		let mut combo_history = vec![];
+		for step in simulation.steps.iter().rev() {
+			combo_history.push((step.success, step.combo));
+			if !step.skipped {
+				break;
+			}
+		}
+
+		Self {
+			progression: simulation.progression,
+			quality: simulation.quality,
+			durability: simulation.durability,
+			available_cp: simulation.available_cp,
+			state: simulation.state(),
+			buffs,
+			combo_history,
+		}
+	}
+}
+
+impl Simulation {
+	/// Computes the exact distribution of outcomes of this simulation's rotation, branching
+	/// on every condition change and on the success roll of every action below 100%.
+	///
+	/// Conditions given in `step_states` and failures forced by `fails` are respected, as
+	/// they are by [`Simulation::start`] with `random_conditions`. Branches that reach an
+	/// identical state are merged, but the number of branches still grows quickly with
+	/// the length of the rotation, so this is meant for short rotations.
+	pub fn exact_outcomes(&self) -> OutcomeDistribution {
+		let mut branches: Vec<(Simulation, f64)> = vec![(self.clone(), 1.0)];
+		let mut finished: Vec<(Simulation, f64)> = vec![];
+
+		for (i, action) in self.actions.iter().enumerate() {
+			let mut merged: HashMap<BranchKey, (Simulation, f64)> = HashMap::new();
+			let mut add_branch = |simulation: Simulation, probability: f64| {
+				merged
+					.entry(BranchKey::new(&simulation))
+					.or_insert((simulation, 0.0))
+					.1 += probability;
+			};
+
+			for (mut simulation, probability) in branches {
+				simulation.set_step_state(i, true);
+
+				// the roll is an integer from 0 to 99, and the action succeeds if its
+				// success rate is at least the roll
+				let success_chance =
+					(action.get_success_rate(&simulation).min(99) + 1) as f64 / 100.0;
+				let mut rolled = vec![];
+				if self.is_forced_fail(i) {
+					simulation.run_step(action, i, false, false, usize::MAX, None);
+					rolled.push((simulation, probability));
+				} else {
+					let mut success_branch = simulation.clone();
+					let result =
+						success_branch.run_step(action, i, false, false, usize::MAX, Some(0));
+					if result.skipped || success_chance >= 1.0 {
+						rolled.push((success_branch, probability));
+					} else {
+						simulation.run_step(action, i, false, false, usize::MAX, Some(999));
+						rolled.push((success_branch, probability * success_chance));
+						rolled.push((simulation, probability * (1.0 - success_chance)));
+					}
+				}
+
+				for (simulation, probability) in rolled {
+					if simulation.success.is_some() {
+						// the remaining steps are all skipped, so the outcome is settled
+						finished.push((simulation, probability));
+					} else if !Self::ticks_state(action) {
+						add_branch(simulation, probability);
+					} else {
+						for (state, rate) in simulation.next_state_distribution() {
+							if rate <= 0.0 {
+								continue;
+							}
+							let mut branch = simulation.clone();
+							branch.override_state(state);
+							add_branch(branch, probability * rate);
+						}
+					}
+				}
+			}
+
+			branches = merged.into_values().collect();
+		}
+		finished.extend(branches);
+
+		let mut outcomes: HashMap<(bool, u32, u32), Outcome> = HashMap::new();
+		for (simulation, probability) in finished {
+			let result = simulation.into_result();
+			outcomes
+				.entry((
+					result.success,
+					result.simulation.progression,
+					result.simulation.quality,
+				))
+				.or_insert(Outcome {
+					probability: 0.0,
+					success: result.success,
+					progression: result.simulation.progression,
+					quality: result.simulation.quality,
+					hq_percent: result.hq_percent,
+				})
+				.probability += probability;
+		}
+		let mut outcomes: Vec<_> = outcomes.into_values().collect();
+		outcomes.sort_by_key(|outcome| (outcome.quality, outcome.progression, outcome.success));
+
+		OutcomeDistribution { outcomes }
+	}
+}
@@ -0,0 +1,151 @@
+use bon::bon;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::types::{
+	structs::{Ingredient, SimulationResult},
+	Simulation,
+};
+
+/// An ingredient that can be used in HQ, and what each HQ unit of it costs.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct HqIngredientOption {
+	pub id: String,
+	pub cost: u32,
+	/// How many HQ units are available. Defaults to the amount the recipe uses.
+	pub available: Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum QualityTarget {
+	HqPercent(u32),
+	Quality(u32),
+}
+impl QualityTarget {
+	/// Whether `result` is a successful craft that reaches this target.
+	pub fn is_reached_by(&self, result: &SimulationResult) -> bool {
+		result.success
+			&& match *self {
+				QualityTarget::HqPercent(hq_percent) => result.hq_percent >= hq_percent,
+				QualityTarget::Quality(quality) => result.simulation.quality >= quality,
+			}
+	}
+}
+
+/// The cheapest HQ ingredients found for a quality target.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct HqIngredientPlan {
+	pub hq_ingredients: Vec<Ingredient>,
+	pub cost: u32,
+	pub starting_quality: u32,
+	/// The quality reached by running the rotation with these ingredients.
+	pub quality: u32,
+	pub hq_percent: u32,
+}
+
+#[bon]
+impl Simulation {
+	/// Finds the cheapest mix of HQ ingredients for which this simulation's rotation reaches
+	/// `target` and the craft succeeds, or `None` if no mix of the available ingredients does.
+	///
+	/// Every candidate is checked by running the rotation in linear mode, with the starting
+	/// quality [`Simulation::builder`] gives those ingredients. Ingredients of the recipe without
+	/// an option can't be used in HQ.
+	#[builder(finish_fn = run)]
+	pub fn optimize_hq_ingredients(
+		&self,
+		options: Vec<HqIngredientOption>,
+		target: QualityTarget,
+	) -> Option<HqIngredientPlan> {
+		let usable: Vec<_> = options
+			.iter()
+			.filter_map(|option| {
+				let ingredient = self
+					.recipe
+					.ingredients
+					.iter()
+					.find(|ingredient| ingredient.id == option.id)?;
+				let weight = self.recipe.hq_weight(&option.id);
+				let max_amount = option
+					.available
+					.unwrap_or(ingredient.amount)
+					.min(ingredient.amount);
+				(weight > 0 && max_amount > 0).then_some((option, weight, max_amount))
+			})
+			.collect();
+
+		// if even every available HQ ingredient isn't enough, no cheaper mix will be
+		let everything = usable
+			.iter()
+			.map(|&(option, _, max_amount)| Ingredient {
+				id: option.id.clone(),
+				amount: max_amount,
+				..Default::default()
+			})
+			.collect();
+		self.try_hq_ingredients(everything, 0, target)?;
+
+		// the cheapest way to reach every possible total weight, which orders starting quality
+		let mut cheapest: HashMap<u32, (u32, Vec<u32>)> = HashMap::from([(0, (0, vec![]))]);
+		for &(option, weight, max_amount) in &usable {
+			let mut next: HashMap<u32, (u32, Vec<u32>)> = HashMap::new();
+			for (total_weight, (cost, amounts)) in &cheapest {
+				for amount in 0..=max_amount {
+					let key = total_weight + weight * amount;
+					let cost = cost + option.cost * amount;
+					if next
+						.get(&key)
+						.map_or(true, |(existing, _)| cost < *existing)
+					{
+						let mut amounts = amounts.clone();
+						amounts.push(amount);
+						next.insert(key, (cost, amounts));
+					}
+				}
+			}
+			cheapest = next;
+		}
+
+		let mut candidates: Vec<_> = cheapest.into_iter().collect();
+		candidates.sort_by_key(|&(total_weight, (cost, _))| (cost, total_weight));
+		candidates.into_iter().find_map(|(_, (cost, amounts))| {
+			let hq_ingredients: Vec<_> = usable
+				.iter()
+				.zip(amounts)
+				.filter(|&(_, amount)| amount > 0)
+				.map(|((option, ..), amount)| Ingredient {
+					id: option.id.clone(),
+					amount,
+					..Default::default()
+				})
+				.collect();
+			self.try_hq_ingredients(hq_ingredients, cost, target)
+		})
+	}
+}
+
+impl Simulation {
+	fn try_hq_ingredients(
+		&self,
+		hq_ingredients: Vec<Ingredient>,
+		cost: u32,
+		target: QualityTarget,
+	) -> Option<HqIngredientPlan> {
+		let mut simulation = self.clone();
+		simulation.set_hq_ingredients(&hq_ingredients);
+		simulation.reset();
+		let starting_quality = simulation.quality;
+		let result = simulation.start().linear(true).run();
+		target.is_reached_by(&result).then_some(HqIngredientPlan {
+			starting_quality,
+			hq_ingredients,
+			cost,
+			quality: result.simulation.quality,
+			hq_percent: result.hq_percent,
+		})
+	}
+}
@@ -0,0 +1,286 @@
+use std::fmt;
+
+use crate::types::{
+	actions,
+	enums::{ActionType, Buff, CraftingActionEnum},
+	structs::SimulationResult,
+	traits::CraftingAction,
+	Simulation,
+};
+
+// the buffs whose whole point is to boost a later action
+const TRACKED_BUFFS: [Buff; 6] = [
+	Buff::Innovation,
+	Buff::GreatStrides,
+	Buff::Veneration,
+	Buff::MuscleMemory,
+	Buff::WasteNot,
+	Buff::WasteNotII,
+];
+const MANIPULATION_REPAIR: u32 = 5;
+const MASTERS_MEND_REPAIR: u32 = 30;
+
+/// A way a rotation wastes CP, durability or steps, found by [`SimulationResult::lint`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct LintWarning {
+	/// The index of the step the warning is about.
+	pub step: usize,
+	pub kind: LintKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LintKind {
+	/// `buff` ran out, or the craft ended, before any action it boosts was used.
+	UnusedBuff { buff: Buff },
+	/// Manipulation was used again while it still had `remaining` steps to go.
+	ManipulationRecast { remaining: i32 },
+	/// Progress would have been complete without this step, which added `progression` of
+	/// the `excess` the craft ended with.
+	ProgressOvershoot { progression: u32, excess: u32 },
+	/// The craft finished below max quality with `cp` CP left, enough for another touch.
+	LeftoverCp { cp: u32 },
+	/// `wasted` durability of a repair went above the recipe's durability.
+	WastedRepair { wasted: u32 },
+	/// Preparatory Touch, or Refined Touch after Basic Touch, was used at 10 Inner Quiet stacks
+	/// and lost the extra stack it pays for. Other touches only lose their single stack, which
+	/// isn't worth a warning as they're still used for their quality.
+	InnerQuietCapped,
+	/// Observe wasn't followed by an action that combos from it.
+	ObserveWithoutCombo,
+}
+
+impl LintWarning {
+	/// How the rotation could be changed to avoid the waste.
+	pub fn suggestion(&self) -> String {
+		match &self.kind {
+			LintKind::UnusedBuff { buff } => format!(
+				"remove {:?}, or use it right before the actions it should boost",
+				buff
+			),
+			LintKind::ManipulationRecast { remaining } => format!(
+				"wait {} more step{} before using Manipulation again",
+				remaining,
+				if *remaining == 1 { "" } else { "s" }
+			),
+			LintKind::ProgressOvershoot { .. } => {
+				"remove this step, or replace it with a quality action".to_string()
+			}
+			LintKind::LeftoverCp { .. } => {
+				"spend the CP on quality actions before finishing the craft".to_string()
+			}
+			LintKind::WastedRepair { .. } => {
+				"use actions that cost durability first, or repair later".to_string()
+			}
+			LintKind::InnerQuietCapped => {
+				"use a cheaper touch, as the extra stack is lost at 10 stacks".to_string()
+			}
+			LintKind::ObserveWithoutCombo => {
+				"follow Observe with an action that combos from it, or remove it".to_string()
+			}
+		}
+	}
+}
+impl fmt::Display for LintWarning {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "step {}: ", self.step + 1)?;
+		match &self.kind {
+			LintKind::UnusedBuff { buff } => write!(f, "{:?} ends without being used", buff),
+			LintKind::ManipulationRecast { remaining } => write!(
+				f,
+				"Manipulation is used again with {} steps remaining",
+				remaining
+			),
+			LintKind::ProgressOvershoot {
+				progression,
+				excess,
+			} => write!(
+				f,
+				"this step adds {} progress, but the craft ends with {} more than needed",
+				progression, excess
+			),
+			LintKind::LeftoverCp { cp } => {
+				write!(f, "the craft ends below max quality with {} CP left", cp)
+			}
+			LintKind::WastedRepair { wasted } => {
+				write!(f, "{} durability is repaired above the maximum", wasted)
+			}
+			LintKind::InnerQuietCapped => write!(f, "Inner Quiet is already at 10 stacks"),
+			LintKind::ObserveWithoutCombo => write!(f, "Observe isn't followed by a combo"),
+		}?;
+		write!(f, " ({})", self.suggestion())
+	}
+}
+
+impl SimulationResult {
+	/// Looks for steps of the rotation that waste CP, durability or buffs, in step order.
+	pub fn lint(&self) -> Vec<LintWarning> {
+		let states = self.replay();
+		let mut warnings = vec![];
+
+		for (index, step) in self.steps.iter().enumerate() {
+			let before = &states[index];
+			let after = &states[index + 1];
+			let mut warn = |kind| warnings.push(LintWarning { step: index, kind });
+			if step.skipped {
+				continue;
+			}
+			let succeeded = step.success == Some(true);
+
+			if let Some(buff) = TRACKED_BUFFS.into_iter().find(|&buff| {
+				after
+					.get_buff(buff)
+					.is_some_and(|b| b.applied_step as usize == index)
+			}) {
+				if !self.is_buff_used(&states, buff, index) {
+					warn(LintKind::UnusedBuff { buff });
+				}
+			}
+
+			if step.action == actions::Manipulation.into() && succeeded {
+				if let Some(manipulation) = before.get_buff(Buff::Manipulation) {
+					warn(LintKind::ManipulationRecast {
+						remaining: manipulation.duration,
+					});
+				}
+			}
+
+			let mut wasted = 0;
+			if step.action == actions::MastersMend.into() && succeeded {
+				wasted += MASTERS_MEND_REPAIR.saturating_sub(step.solidity_difference as u32);
+			}
+			if Self::manipulation_ticked(before, after, index) {
+				let repaired = step
+					.after_buff_tick
+					.as_ref()
+					.map_or(0, |tick| tick.solidity_difference.max(0) as u32);
+				wasted += MANIPULATION_REPAIR.saturating_sub(repaired);
+			}
+			if wasted > 0 {
+				warn(LintKind::WastedRepair { wasted });
+			}
+
+			// only the touches that grant an extra stack, every other touch at 10 stacks is fine
+			let adds_extra_stack = step.action == actions::PreparatoryTouch.into()
+				|| (step.action == actions::RefinedTouch.into() && step.combo == Some(true));
+			let stacks = before.get_buff(Buff::InnerQuiet).map_or(0, |b| b.stacks);
+			if adds_extra_stack && succeeded && stacks >= 10 {
+				warn(LintKind::InnerQuietCapped);
+			}
+
+			if step.action == actions::Observe.into() && succeeded {
+				let combos = self.steps[index + 1..]
+					.iter()
+					.find(|next| !next.skipped)
+					.is_some_and(|next| {
+						next.combo == Some(true)
+							&& [
+								actions::AdvancedTouch.into(),
+								actions::FocusedSynthesis.into(),
+								actions::FocusedTouch.into(),
+							]
+							.contains(&next.action)
+					});
+				if !combos {
+					warn(LintKind::ObserveWithoutCombo);
+				}
+			}
+		}
+
+		if let Some(warning) = self.progress_overshoot() {
+			warnings.push(warning);
+		}
+		if let Some(warning) = self.leftover_cp() {
+			warnings.push(warning);
+		}
+		warnings.sort_by_key(|warning| warning.step);
+		warnings
+	}
+
+	// the simulation before each step and after the last one, with each step replayed in
+	// its recorded condition and with its recorded outcome
+	fn replay(&self) -> Vec<Simulation> {
+		let mut simulation = self.simulation.clone();
+		simulation.reset();
+		let mut states = vec![simulation.clone()];
+		for (index, step) in self.steps.iter().enumerate() {
+			simulation.override_state(step.state);
+			let roll = if step.success == Some(false) { 999 } else { 0 };
+			simulation.run_step(&step.action, index, false, false, usize::MAX, Some(roll));
+			states.push(simulation.clone());
+		}
+		states
+	}
+
+	// whether an action boosted by the `buff` applied at step `applied` was used while it
+	// lasted
+	fn is_buff_used(&self, states: &[Simulation], buff: Buff, applied: usize) -> bool {
+		self.steps
+			.iter()
+			.enumerate()
+			.skip(applied + 1)
+			.take_while(|&(index, _)| {
+				states[index]
+					.get_buff(buff)
+					.is_some_and(|b| b.applied_step as usize == applied)
+			})
+			.filter(|(_, step)| !step.skipped)
+			.any(|(index, step)| match buff {
+				Buff::Innovation | Buff::GreatStrides => {
+					step.action.get_type() == ActionType::Quality
+				}
+				Buff::Veneration | Buff::MuscleMemory => {
+					step.action.get_type() == ActionType::Progression
+				}
+				_ => step.action.get_durability_cost(&states[index]) > 0,
+			})
+	}
+
+	// whether Manipulation repaired durability after step `index`
+	fn manipulation_ticked(before: &Simulation, after: &Simulation, index: usize) -> bool {
+		let step = &after.steps[index];
+		let recast = step.action == actions::Manipulation.into() && step.success == Some(true);
+		let skips_ticks = step.action.skips_buff_ticks()
+			|| (step.success != Some(true) && step.action.skip_on_fail());
+		before
+			.get_buff(Buff::Manipulation)
+			.is_some_and(|b| (b.applied_step as usize) < index)
+			&& !recast
+			&& !skips_ticks
+			&& after.success.is_none()
+	}
+
+	// the cheapest progress step that the craft could have done without
+	fn progress_overshoot(&self) -> Option<LintWarning> {
+		let excess = self
+			.simulation
+			.progression
+			.checked_sub(self.simulation.recipe.progress)?;
+		self.steps
+			.iter()
+			.enumerate()
+			.filter(|(_, step)| step.added_progression > 0 && step.added_progression <= excess)
+			.min_by_key(|(_, step)| step.added_progression)
+			.map(|(index, step)| LintWarning {
+				step: index,
+				kind: LintKind::ProgressOvershoot {
+					progression: step.added_progression,
+					excess,
+				},
+			})
+	}
+
+	fn leftover_cp(&self) -> Option<LintWarning> {
+		let simulation = &self.simulation;
+		let last_step = self.steps.iter().rposition(|step| !step.skipped)?;
+		let touch: CraftingActionEnum = actions::BasicTouch.into();
+		(self.success
+			&& simulation.quality < simulation.recipe.quality
+			&& simulation.available_cp >= touch.get_base_cp_cost(simulation))
+		.then_some(LintWarning {
+			step: last_step,
+			kind: LintKind::LeftoverCp {
+				cp: simulation.available_cp,
+			},
+		})
+	}
+}
@@ -1,6 +1,39 @@
 mod simulation;
 pub use simulation::*;
 
+mod batch;
+pub use batch::*;
+
+mod exact;
+pub use exact::*;
+
+mod stepper;
+pub use stepper::*;
+
+mod policy;
+pub use policy::*;
+
+mod script;
+pub use script::*;
+
+mod quick_synth;
+pub use quick_synth::*;
+
+mod hq_ingredients;
+pub use hq_ingredients::*;
+
+mod consumables;
+pub use consumables::*;
+
+mod conditions;
+pub use conditions::*;
+
+mod lint;
+pub use lint::*;
+
+mod comparison;
+pub use comparison::*;
+
 pub mod actions;
 pub mod enums;
 pub mod structs;
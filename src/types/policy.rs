@@ -0,0 +1,73 @@
+use bon::bon;
+
+use crate::types::{
+	batch::BatchResult, enums::CraftingActionEnum, structs::SimulationResult, Simulation,
+};
+
+/// How many actions [`Simulation::run_script`] and [`Simulation::run_policy`] use at most,
+/// unless given another `max_steps`.
+pub const DEFAULT_RUNNER_MAX_STEPS: usize = 100;
+
+/// Picks each action of a craft from the state it's in, instead of following a fixed
+/// rotation.
+///
+/// Implemented for closures taking the simulation, so a heuristic can be written inline.
+pub trait Policy {
+	/// The action to use next. `simulation` is the craft so far, with its `state` set to the
+	/// condition the action will be used in.
+	fn next_action(&mut self, simulation: &Simulation) -> CraftingActionEnum;
+}
+impl<F: FnMut(&Simulation) -> CraftingActionEnum> Policy for F {
+	fn next_action(&mut self, simulation: &Simulation) -> CraftingActionEnum {
+		self(simulation)
+	}
+}
+
+#[bon]
+impl Simulation {
+	/// Runs a craft with the actions picked by `policy`, with random conditions and success
+	/// rolls. Its `actions` are ignored, and replaced by the actions used.
+	///
+	/// The craft ends once it is finished, or after `max_steps`
+	/// ([`DEFAULT_RUNNER_MAX_STEPS`] by default) actions.
+	#[builder(finish_fn = run)]
+	pub fn run_policy<P: Policy>(
+		mut self,
+		policy: &mut P,
+		safe: Option<bool>,
+		max_steps: Option<usize>,
+	) -> SimulationResult {
+		let safe = safe.unwrap_or(false);
+		let max_steps = max_steps.unwrap_or(DEFAULT_RUNNER_MAX_STEPS);
+
+		self.actions.clear();
+		self.set_step_state(0, true);
+		while self.success.is_none() && self.steps.len() < max_steps {
+			let action = policy.next_action(&self);
+			self.step_action(action, false, safe, true);
+		}
+		self.into_result()
+	}
+
+	/// Runs `runs` crafts with the actions picked by `policy`, as [`Simulation::batch`]
+	/// does for a fixed rotation. Each run starts from a fresh clone of `policy`.
+	#[builder(finish_fn = run)]
+	pub fn policy_batch<P: Policy + Clone + Sync>(
+		&self,
+		policy: &P,
+		runs: usize,
+		seed: Option<u64>,
+		/// Defaults to the available parallelism of the machine.
+		threads: Option<usize>,
+		safe: Option<bool>,
+		max_steps: Option<usize>,
+	) -> BatchResult {
+		self.run_batch(runs, seed, threads, |sim| {
+			sim.run_policy()
+				.policy(&mut policy.clone())
+				.maybe_safe(safe)
+				.maybe_max_steps(max_steps)
+				.run()
+		})
+	}
+}
@@ -0,0 +1,68 @@
+use bon::bon;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::types::{actions, traits::CraftingAction, Simulation};
+
+/// Expected results of a batch of Quick Synthesis attempts.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct QuickSynthResult {
+	pub attempts: u32,
+	/// Chance for a single attempt to succeed.
+	pub success_rate: f64,
+	pub expected_successes: f64,
+	pub expected_hq: f64,
+	/// Expected amount of each ingredient lost to failed attempts, by ingredient id,
+	/// assuming no material is retained on failure.
+	pub expected_material_loss: HashMap<String, f64>,
+}
+
+#[bon]
+impl Simulation {
+	/// Predicts the results of `attempts` Quick Synthesis attempts of this simulation's
+	/// recipe, or `None` if the recipe can't be quick synthesized.
+	///
+	/// Each attempt is modelled as the game using Basic Synthesis until the craft is done or
+	/// runs out of durability, under random conditions. This simulation's rotation is ignored,
+	/// but its starting quality from HQ ingredients is kept.
+	#[builder(finish_fn = run)]
+	pub fn quick_synthesis(&self, attempts: u32) -> Option<QuickSynthResult> {
+		if self.recipe.quick_synth != Some(true) {
+			return None;
+		}
+
+		// enough steps to run out of durability, even if every step were Sturdy
+		let durability_cost = actions::BasicSynthesis.get_durability_cost(self).max(1);
+		let max_steps = (self.recipe.durability / durability_cost * 2 + 1) as usize;
+		let mut simulation = self.clone();
+		simulation.actions = vec![actions::BasicSynthesis.into(); max_steps];
+		simulation.reset();
+
+		let outcomes = simulation.exact_outcomes();
+		let success_rate = outcomes.success_probability();
+		let hq_rate: f64 = outcomes
+			.outcomes
+			.iter()
+			.filter(|outcome| outcome.success)
+			.map(|outcome| outcome.probability * outcome.hq_percent as f64 / 100.0)
+			.sum();
+
+		let expected_failures = attempts as f64 * (1.0 - success_rate);
+		let mut expected_material_loss = HashMap::new();
+		for ingredient in &self.recipe.ingredients {
+			*expected_material_loss
+				.entry(ingredient.id.clone())
+				.or_insert(0.0) += ingredient.amount as f64 * expected_failures;
+		}
+
+		Some(QuickSynthResult {
+			attempts,
+			success_rate,
+			expected_successes: attempts as f64 * success_rate,
+			expected_hq: attempts as f64 * hq_rate,
+			expected_material_loss,
+		})
+	}
+}
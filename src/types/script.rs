@@ -0,0 +1,693 @@
+//! Rotations that react to the state of the craft as it goes.
+//!
+//! A script is a list of actions, `if` branches and `repeat` loops, written one per line
+//! with actions named as in [`CraftingActionEnum::name`]. Everything after a `#` is a
+//! comment.
+//!
+//! ```text
+//! MuscleMemory
+//! Manipulation
+//! repeat until progress >= max or finished {
+//!     if state == Good {
+//!         TricksOfTheTrade
+//!     } else if state == Sturdy and cp >= 18 {
+//!         Groundwork
+//!     } else {
+//!         CarefulSynthesis
+//!     }
+//! }
+//! repeat 3 {
+//!     BasicTouch
+//! }
+//! if stacks InnerQuiet >= 10 and not buff Innovation {
+//!     Innovation
+//! }
+//! ByregotsBlessing
+//! ```
+//!
+//! Conditions compare `progress`, `quality`, `durability` or `cp` against a number or
+//! `max`, check the current `state`, whether a `buff` is active or how many `stacks` it
+//! has, or whether the craft is `finished`. They can be combined with `and`, `or`, `not`
+//! and parentheses.
+
+use bon::bon;
+use num_traits::FromPrimitive;
+use std::{fmt, str::FromStr};
+
+use crate::types::{
+	enums::{Buff, CraftingActionEnum, StepState},
+	structs::SimulationResult,
+	Simulation, DEFAULT_RUNNER_MAX_STEPS,
+};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Script {
+	pub steps: Vec<ScriptStep>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScriptStep {
+	Action(CraftingActionEnum),
+	If {
+		condition: ScriptCondition,
+		then: Vec<ScriptStep>,
+		otherwise: Vec<ScriptStep>,
+	},
+	RepeatTimes {
+		times: u32,
+		steps: Vec<ScriptStep>,
+	},
+	/// Runs `steps` for as long as `condition` doesn't hold, checking it before each pass.
+	RepeatUntil {
+		condition: ScriptCondition,
+		steps: Vec<ScriptStep>,
+	},
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScriptCondition {
+	State(StepState),
+	Buff(Buff),
+	Stacks(Buff, Comparison, u32),
+	Compare(Metric, Comparison, Threshold),
+	/// The craft is over, whether it succeeded or ran out of durability.
+	Finished,
+	Not(Box<ScriptCondition>),
+	And(Box<ScriptCondition>, Box<ScriptCondition>),
+	Or(Box<ScriptCondition>, Box<ScriptCondition>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metric {
+	Progress,
+	Quality,
+	Durability,
+	Cp,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Threshold {
+	Value(u32),
+	/// The recipe's progress, quality or durability, or the crafter's maximum CP.
+	Max,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+	Less,
+	LessOrEqual,
+	Greater,
+	GreaterOrEqual,
+	Equal,
+	NotEqual,
+}
+impl Comparison {
+	pub fn compare(&self, left: i64, right: i64) -> bool {
+		match self {
+			Comparison::Less => left < right,
+			Comparison::LessOrEqual => left <= right,
+			Comparison::Greater => left > right,
+			Comparison::GreaterOrEqual => left >= right,
+			Comparison::Equal => left == right,
+			Comparison::NotEqual => left != right,
+		}
+	}
+
+	fn symbol(&self) -> &'static str {
+		match self {
+			Comparison::Less => "<",
+			Comparison::LessOrEqual => "<=",
+			Comparison::Greater => ">",
+			Comparison::GreaterOrEqual => ">=",
+			Comparison::Equal => "==",
+			Comparison::NotEqual => "!=",
+		}
+	}
+}
+
+impl ScriptCondition {
+	pub fn is_met(&self, simulation: &Simulation) -> bool {
+		match self {
+			ScriptCondition::State(state) => simulation.state() == *state,
+			ScriptCondition::Buff(buff) => simulation.has_buff(*buff),
+			ScriptCondition::Stacks(buff, comparison, stacks) => comparison.compare(
+				simulation.get_buff(*buff).map_or(0, |b| b.stacks) as i64,
+				*stacks as i64,
+			),
+			ScriptCondition::Compare(metric, comparison, threshold) => {
+				let (value, max) = match metric {
+					Metric::Progress => (
+						simulation.progression as i64,
+						simulation.recipe.progress as i64,
+					),
+					Metric::Quality => {
+						(simulation.quality as i64, simulation.recipe.quality as i64)
+					}
+					Metric::Durability => (
+						simulation.durability as i64,
+						simulation.recipe.durability as i64,
+					),
+					Metric::Cp => (simulation.available_cp as i64, simulation.max_cp as i64),
+				};
+				let threshold = match threshold {
+					Threshold::Value(value) => *value as i64,
+					Threshold::Max => max,
+				};
+				comparison.compare(value, threshold)
+			}
+			ScriptCondition::Finished => simulation.success.is_some(),
+			ScriptCondition::Not(condition) => !condition.is_met(simulation),
+			ScriptCondition::And(left, right) => {
+				left.is_met(simulation) && right.is_met(simulation)
+			}
+			ScriptCondition::Or(left, right) => left.is_met(simulation) || right.is_met(simulation),
+		}
+	}
+}
+
+impl From<Vec<CraftingActionEnum>> for Script {
+	fn from(actions: Vec<CraftingActionEnum>) -> Self {
+		Self {
+			steps: actions.into_iter().map(ScriptStep::Action).collect(),
+		}
+	}
+}
+
+#[bon]
+impl Simulation {
+	/// Runs `script` on this simulation, picking each action according to the state of the
+	/// craft at that step. Its `actions` are ignored, and replaced by the actions used.
+	///
+	/// Unless `linear`, conditions are rolled after every step as in game. The run stops
+	/// when the script ends, the craft is finished, or `max_steps`
+	/// ([`DEFAULT_RUNNER_MAX_STEPS`] by default) actions were used, which also ends loops whose
+	/// condition is never met.
+	#[builder(finish_fn = run)]
+	pub fn run_script(
+		mut self,
+		script: &Script,
+		linear: Option<bool>,
+		safe: Option<bool>,
+		max_steps: Option<usize>,
+	) -> SimulationResult {
+		let linear = linear.unwrap_or(false);
+		self.actions.clear();
+		self.set_step_state(0, !linear);
+
+		let mut runner = ScriptRunner {
+			simulation: self,
+			linear,
+			safe: safe.unwrap_or(false),
+			max_steps: max_steps.unwrap_or(DEFAULT_RUNNER_MAX_STEPS),
+		};
+		runner.run(&script.steps);
+		runner.simulation.into_result()
+	}
+}
+
+struct ScriptRunner {
+	simulation: Simulation,
+	linear: bool,
+	safe: bool,
+	max_steps: usize,
+}
+impl ScriptRunner {
+	fn is_done(&self) -> bool {
+		self.simulation.success.is_some() || self.simulation.steps.len() >= self.max_steps
+	}
+
+	// runs one pass of a loop, and returns whether the loop should go on
+	fn run_pass(&mut self, steps: &[ScriptStep]) -> bool {
+		if self.is_done() {
+			return false;
+		}
+		let steps_before = self.simulation.steps.len();
+		self.run(steps);
+		// a pass that uses no action can't change the outcome of the next one
+		self.simulation.steps.len() != steps_before
+	}
+
+	fn run(&mut self, steps: &[ScriptStep]) {
+		for step in steps {
+			if self.is_done() {
+				return;
+			}
+			match step {
+				ScriptStep::Action(action) => {
+					self.simulation
+						.step_action(*action, self.linear, self.safe, !self.linear);
+				}
+				ScriptStep::If {
+					condition,
+					then,
+					otherwise,
+				} => {
+					if condition.is_met(&self.simulation) {
+						self.run(then);
+					} else {
+						self.run(otherwise);
+					}
+				}
+				ScriptStep::RepeatTimes { times, steps } => {
+					for _ in 0..*times {
+						if !self.run_pass(steps) {
+							break;
+						}
+					}
+				}
+				ScriptStep::RepeatUntil { condition, steps } => {
+					while !condition.is_met(&self.simulation) {
+						if !self.run_pass(steps) {
+							break;
+						}
+					}
+				}
+			}
+		}
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ScriptParseError {
+	/// The action named on `line` (zero-indexed) doesn't exist.
+	UnknownAction {
+		line: usize,
+		name: String,
+	},
+	UnknownBuff {
+		line: usize,
+		name: String,
+	},
+	UnknownState {
+		line: usize,
+		name: String,
+	},
+	/// `found` was on `line` where `expected` should have been.
+	Unexpected {
+		line: usize,
+		found: String,
+		expected: &'static str,
+	},
+	/// The script ended where `expected` should have been.
+	UnexpectedEnd {
+		expected: &'static str,
+	},
+}
+impl fmt::Display for ScriptParseError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ScriptParseError::UnknownAction { line, name } => {
+				write!(f, "unknown action `{}` on line {}", name, line + 1)
+			}
+			ScriptParseError::UnknownBuff { line, name } => {
+				write!(f, "unknown buff `{}` on line {}", name, line + 1)
+			}
+			ScriptParseError::UnknownState { line, name } => {
+				write!(f, "unknown condition `{}` on line {}", name, line + 1)
+			}
+			ScriptParseError::Unexpected {
+				line,
+				found,
+				expected,
+			} => write!(
+				f,
+				"expected {} on line {}, found `{}`",
+				expected,
+				line + 1,
+				found
+			),
+			ScriptParseError::UnexpectedEnd { expected } => {
+				write!(f, "expected {}, found the end of the script", expected)
+			}
+		}
+	}
+}
+impl std::error::Error for ScriptParseError {}
+
+impl Script {
+	pub fn parse(text: &str) -> Result<Script, ScriptParseError> {
+		let mut parser = Parser {
+			tokens: tokenize(text),
+			position: 0,
+		};
+		let steps = parser.steps()?;
+		match parser.next() {
+			None => Ok(Script { steps }),
+			Some(token) => Err(token.unexpected("an action, `if` or `repeat`")),
+		}
+	}
+}
+impl FromStr for Script {
+	type Err = ScriptParseError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Script::parse(s)
+	}
+}
+
+struct Token<'a> {
+	text: &'a str,
+	line: usize,
+}
+impl Token<'_> {
+	fn unexpected(&self, expected: &'static str) -> ScriptParseError {
+		ScriptParseError::Unexpected {
+			line: self.line,
+			found: self.text.to_string(),
+			expected,
+		}
+	}
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+	let mut tokens = vec![];
+	for (line, content) in text.lines().enumerate() {
+		let content = content.split('#').next().unwrap_or_default();
+		let mut rest = content.trim_start();
+		while let Some(first) = rest.chars().next() {
+			let len = if first.is_alphanumeric() || first == '_' {
+				rest.find(|c: char| !c.is_alphanumeric() && c != '_')
+					.unwrap_or(rest.len())
+			} else if ["<=", ">=", "==", "!="]
+				.iter()
+				.any(|operator| rest.starts_with(operator))
+			{
+				2
+			} else {
+				first.len_utf8()
+			};
+			tokens.push(Token {
+				text: &rest[..len],
+				line,
+			});
+			rest = rest[len..].trim_start();
+		}
+	}
+	tokens
+}
+
+struct Parser<'a> {
+	tokens: Vec<Token<'a>>,
+	position: usize,
+}
+impl<'a> Parser<'a> {
+	fn peek(&self) -> Option<&'a str> {
+		self.tokens.get(self.position).map(|token| token.text)
+	}
+
+	fn next(&mut self) -> Option<&Token<'a>> {
+		let token = self.tokens.get(self.position)?;
+		self.position += 1;
+		Some(token)
+	}
+
+	fn eat(&mut self, text: &str) -> bool {
+		let matches = self.peek() == Some(text);
+		if matches {
+			self.position += 1;
+		}
+		matches
+	}
+
+	fn expect(&mut self, expected: &'static str) -> Result<&Token<'a>, ScriptParseError> {
+		match self.next() {
+			Some(token) if token.text == expected => Ok(token),
+			Some(token) => Err(token.unexpected(expected)),
+			None => Err(ScriptParseError::UnexpectedEnd { expected }),
+		}
+	}
+
+	fn next_or_end(&mut self, expected: &'static str) -> Result<&Token<'a>, ScriptParseError> {
+		self.next()
+			.ok_or(ScriptParseError::UnexpectedEnd { expected })
+	}
+
+	// steps until the end of the script or of the enclosing block
+	fn steps(&mut self) -> Result<Vec<ScriptStep>, ScriptParseError> {
+		let mut steps = vec![];
+		while self.peek().is_some_and(|text| text != "}") {
+			steps.push(self.step()?);
+		}
+		Ok(steps)
+	}
+
+	fn block(&mut self) -> Result<Vec<ScriptStep>, ScriptParseError> {
+		self.expect("{")?;
+		let steps = self.steps()?;
+		self.expect("}")?;
+		Ok(steps)
+	}
+
+	fn step(&mut self) -> Result<ScriptStep, ScriptParseError> {
+		if self.eat("if") {
+			let condition = self.condition()?;
+			let then = self.block()?;
+			let otherwise = if !self.eat("else") {
+				vec![]
+			} else if self.peek() == Some("if") {
+				vec![self.step()?]
+			} else {
+				self.block()?
+			};
+			return Ok(ScriptStep::If {
+				condition,
+				then,
+				otherwise,
+			});
+		}
+		if self.eat("repeat") {
+			if self.eat("until") {
+				let condition = self.condition()?;
+				let steps = self.block()?;
+				return Ok(ScriptStep::RepeatUntil { condition, steps });
+			}
+			let times = self.number("a number of times or `until`")?;
+			let steps = self.block()?;
+			return Ok(ScriptStep::RepeatTimes { times, steps });
+		}
+
+		let token = self.next_or_end("an action")?;
+		CraftingActionEnum::from_name(token.text)
+			.map(ScriptStep::Action)
+			.ok_or_else(|| ScriptParseError::UnknownAction {
+				line: token.line,
+				name: token.text.to_string(),
+			})
+	}
+
+	fn condition(&mut self) -> Result<ScriptCondition, ScriptParseError> {
+		let mut condition = self.and_condition()?;
+		while self.eat("or") {
+			condition = ScriptCondition::Or(Box::new(condition), Box::new(self.and_condition()?));
+		}
+		Ok(condition)
+	}
+
+	fn and_condition(&mut self) -> Result<ScriptCondition, ScriptParseError> {
+		let mut condition = self.unary_condition()?;
+		while self.eat("and") {
+			condition =
+				ScriptCondition::And(Box::new(condition), Box::new(self.unary_condition()?));
+		}
+		Ok(condition)
+	}
+
+	fn unary_condition(&mut self) -> Result<ScriptCondition, ScriptParseError> {
+		if self.eat("not") {
+			return Ok(ScriptCondition::Not(Box::new(self.unary_condition()?)));
+		}
+		if self.eat("(") {
+			let condition = self.condition()?;
+			self.expect(")")?;
+			return Ok(condition);
+		}
+
+		let token = self.next_or_end("a condition")?;
+		let metric = match token.text {
+			"finished" => return Ok(ScriptCondition::Finished),
+			"state" => {
+				let negated = match self.next_or_end("`==` or `!=`")? {
+					token if token.text == "==" => false,
+					token if token.text == "!=" => true,
+					token => return Err(token.unexpected("`==` or `!=`")),
+				};
+				let state = ScriptCondition::State(self.state()?);
+				return Ok(if negated {
+					ScriptCondition::Not(Box::new(state))
+				} else {
+					state
+				});
+			}
+			"buff" => return Ok(ScriptCondition::Buff(self.buff()?)),
+			"stacks" => {
+				let buff = self.buff()?;
+				let comparison = self.comparison()?;
+				let stacks = self.number("a number of stacks")?;
+				return Ok(ScriptCondition::Stacks(buff, comparison, stacks));
+			}
+			"progress" => Metric::Progress,
+			"quality" => Metric::Quality,
+			"durability" => Metric::Durability,
+			"cp" => Metric::Cp,
+			_ => return Err(token.unexpected("a condition")),
+		};
+		let comparison = self.comparison()?;
+		let threshold = if self.eat("max") {
+			Threshold::Max
+		} else {
+			Threshold::Value(self.number("a number or `max`")?)
+		};
+		Ok(ScriptCondition::Compare(metric, comparison, threshold))
+	}
+
+	fn comparison(&mut self) -> Result<Comparison, ScriptParseError> {
+		let token = self.next_or_end("a comparison")?;
+		Ok(match token.text {
+			"<" => Comparison::Less,
+			"<=" => Comparison::LessOrEqual,
+			">" => Comparison::Greater,
+			">=" => Comparison::GreaterOrEqual,
+			"==" => Comparison::Equal,
+			"!=" => Comparison::NotEqual,
+			_ => return Err(token.unexpected("a comparison")),
+		})
+	}
+
+	fn number(&mut self, expected: &'static str) -> Result<u32, ScriptParseError> {
+		let token = self.next_or_end(expected)?;
+		token.text.parse().map_err(|_| token.unexpected(expected))
+	}
+
+	fn buff(&mut self) -> Result<Buff, ScriptParseError> {
+		let token = self.next_or_end("a buff")?;
+		Buff::ALL
+			.into_iter()
+			.find(|buff| format!("{:?}", buff) == token.text)
+			.ok_or_else(|| ScriptParseError::UnknownBuff {
+				line: token.line,
+				name: token.text.to_string(),
+			})
+	}
+
+	fn state(&mut self) -> Result<StepState, ScriptParseError> {
+		let token = self.next_or_end("a condition")?;
+		(1..)
+			.map_while(StepState::from_usize)
+			.find(|state| format!("{:?}", state) == token.text)
+			.ok_or_else(|| ScriptParseError::UnknownState {
+				line: token.line,
+				name: token.text.to_string(),
+			})
+	}
+}
+
+impl fmt::Display for Script {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write_steps(f, &self.steps, 0)
+	}
+}
+
+fn write_steps(f: &mut fmt::Formatter<'_>, steps: &[ScriptStep], depth: usize) -> fmt::Result {
+	let indent = "\t".repeat(depth);
+	for step in steps {
+		write!(f, "{}", indent)?;
+		write_step(f, step, depth)?;
+		writeln!(f)?;
+	}
+	Ok(())
+}
+
+fn write_step(f: &mut fmt::Formatter<'_>, step: &ScriptStep, depth: usize) -> fmt::Result {
+	let indent = "\t".repeat(depth);
+	match step {
+		ScriptStep::Action(action) => write!(f, "{}", action.name()),
+		ScriptStep::If {
+			condition,
+			then,
+			otherwise,
+		} => {
+			writeln!(f, "if {} {{", condition)?;
+			write_steps(f, then, depth + 1)?;
+			write!(f, "{}}}", indent)?;
+			match otherwise.as_slice() {
+				[] => Ok(()),
+				[else_if @ ScriptStep::If { .. }] => {
+					write!(f, " else ")?;
+					write_step(f, else_if, depth)
+				}
+				otherwise => {
+					writeln!(f, " else {{")?;
+					write_steps(f, otherwise, depth + 1)?;
+					write!(f, "{}}}", indent)
+				}
+			}
+		}
+		ScriptStep::RepeatTimes { times, steps } => {
+			writeln!(f, "repeat {} {{", times)?;
+			write_steps(f, steps, depth + 1)?;
+			write!(f, "{}}}", indent)
+		}
+		ScriptStep::RepeatUntil { condition, steps } => {
+			writeln!(f, "repeat until {} {{", condition)?;
+			write_steps(f, steps, depth + 1)?;
+			write!(f, "{}}}", indent)
+		}
+	}
+}
+
+impl fmt::Display for ScriptCondition {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		// parenthesize operands that bind more loosely than their operator, and right hand
+		// operands of the same operator, so the text parses back to the same tree
+		let operand = |f: &mut fmt::Formatter<'_>, condition: &ScriptCondition, parens: bool| {
+			if parens {
+				write!(f, "({})", condition)
+			} else {
+				write!(f, "{}", condition)
+			}
+		};
+		match self {
+			ScriptCondition::State(state) => write!(f, "state == {:?}", state),
+			ScriptCondition::Buff(buff) => write!(f, "buff {:?}", buff),
+			ScriptCondition::Stacks(buff, comparison, stacks) => {
+				write!(f, "stacks {:?} {} {}", buff, comparison.symbol(), stacks)
+			}
+			ScriptCondition::Compare(metric, comparison, threshold) => {
+				let metric = match metric {
+					Metric::Progress => "progress",
+					Metric::Quality => "quality",
+					Metric::Durability => "durability",
+					Metric::Cp => "cp",
+				};
+				write!(f, "{} {} ", metric, comparison.symbol())?;
+				match threshold {
+					Threshold::Value(value) => write!(f, "{}", value),
+					Threshold::Max => write!(f, "max"),
+				}
+			}
+			ScriptCondition::Finished => write!(f, "finished"),
+			ScriptCondition::Not(condition) => {
+				write!(f, "not ")?;
+				let parens = matches!(
+					**condition,
+					ScriptCondition::And(..) | ScriptCondition::Or(..)
+				);
+				operand(f, condition, parens)
+			}
+			ScriptCondition::And(left, right) => {
+				operand(f, left, matches!(**left, ScriptCondition::Or(..)))?;
+				write!(f, " and ")?;
+				operand(
+					f,
+					right,
+					matches!(**right, ScriptCondition::And(..) | ScriptCondition::Or(..)),
+				)
+			}
+			ScriptCondition::Or(left, right) => {
+				operand(f, left, false)?;
+				write!(f, " or ")?;
+				operand(f, right, matches!(**right, ScriptCondition::Or(..)))
+			}
+		}
+	}
+}
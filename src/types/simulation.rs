@@ -1,16 +1,18 @@
 use bon::bon;
-use num_traits::FromPrimitive;
-use rand::{random, Rng};
-use std::collections::{HashMap, HashSet};
-
+use rand::{rngs::StdRng, Rng, SeedableRng};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use crate::types::{
 	actions,
-	enums::{Buff, CraftingActionEnum, FailCause, StepState},
+	enums::{Buff, CrafterTrait, CraftingActionEnum, FailCause, GameVersion, StepState},
 	structs::*,
 	tables,
-	traits::CraftingAction,
+	traits::{CraftingAction, SimulationRng},
+	ConditionRates, ConditionSet,
 };
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Simulation {
 	pub recipe: Craft,
 	pub crafter_stats: CrafterStats,
@@ -43,7 +45,15 @@ pub struct Simulation {
 
 	pub safe: bool,
 
-	possible_conditions: HashSet<StepState>,
+	#[cfg_attr(feature = "serde", serde(default))]
+	pub game_version: GameVersion,
+
+	possible_conditions: ConditionSet,
+	#[cfg_attr(feature = "serde", serde(default))]
+	condition_rates: ConditionRates,
+
+	#[cfg_attr(feature = "serde", serde(skip, default = "Simulation::default_rng"))]
+	rng: Box<dyn SimulationRng>,
 }
 
 #[bon]
@@ -57,36 +67,27 @@ impl Simulation {
 		hq_ingredients: Option<Vec<Ingredient>>,
 		step_states: Option<Vec<StepState>>,
 		fails: Option<Vec<usize>>,
+		/// Seeds the random number generator, making runs reproducible.
+		/// Ignored if `rng` is given.
+		seed: Option<u64>,
+		/// The random number generator used for success rolls and condition changes.
+		rng: Option<Box<dyn SimulationRng>>,
+		/// The game version whose crafting rules are simulated. Defaults to the latest.
+		game_version: Option<GameVersion>,
+		/// The chance of each condition to follow a step. Defaults to the game's rates.
+		condition_rates: Option<ConditionRates>,
 	) -> Self {
-		let mut starting_quality = 0;
-		if let Some(hq_ingredients) = &hq_ingredients {
-			for hq_ingredient in hq_ingredients {
-				if let Some(ingredient) = recipe
-					.ingredients
-					.iter()
-					.find(|&recipe_ingredient| recipe_ingredient.id == hq_ingredient.id)
-				{
-					starting_quality += ingredient.quality.unwrap_or(0) * hq_ingredient.amount;
-				}
-			}
-		}
+		let starting_quality =
+			recipe.starting_quality(hq_ingredients.as_deref().unwrap_or_default());
 
-		let binary_string = format!("{:b}", recipe.conditions_flag);
-		let possible_conditions = binary_string
-			.chars()
-			.rev()
-			.enumerate()
-			.filter_map(|(ix, chr)| {
-				if chr == '1' {
-					StepState::from_usize(ix + 1)
-				} else {
-					None
-				}
-			})
-			.collect();
+		let possible_conditions = recipe.conditions();
 
 		let durability = recipe.durability as i32;
 		let max_cp = crafter_stats.cp;
+		let rng = rng.unwrap_or_else(|| match seed {
+			Some(seed) => Box::new(StdRng::seed_from_u64(seed)),
+			None => Self::default_rng(),
+		});
 
 		Self {
 			recipe,
@@ -106,10 +107,28 @@ impl Simulation {
 			steps: vec![],
 			last_possible_reclaim_step: None,
 			safe: false,
+			game_version: game_version.unwrap_or_default(),
 			possible_conditions,
+			condition_rates: condition_rates.unwrap_or_default(),
+			rng,
 		}
 	}
 
+	fn default_rng() -> Box<dyn SimulationRng> {
+		Box::new(StdRng::from_rng(&mut rand::rng()))
+	}
+
+	/// Replaces the random number generator with one seeded from `seed`.
+	pub fn reseed(&mut self, seed: u64) {
+		self.rng = Box::new(StdRng::seed_from_u64(seed));
+	}
+
+	/// The index of the last step at which Reclaim could still have been used, or `None` if
+	/// it could never be used.
+	pub fn last_possible_reclaim_step(&self) -> Option<u32> {
+		self.last_possible_reclaim_step
+	}
+
 	pub fn state(&self) -> StepState {
 		self.state
 	}
@@ -159,6 +178,12 @@ impl Simulation {
 		self.available_cp = self.max_cp;
 		self.state = StepState::Normal;
 		self.safe = false;
+		self.last_possible_reclaim_step = None;
+	}
+
+	// uses the starting quality of `hq_ingredients` from the next `reset` on
+	pub(crate) fn set_hq_ingredients(&mut self, hq_ingredients: &[Ingredient]) {
+		self.starting_quality = self.recipe.starting_quality(hq_ingredients);
 	}
 
 	#[builder(finish_fn = run)]
@@ -166,11 +191,15 @@ impl Simulation {
 		mut self,
 		linear: Option<bool>,
 		max_steps: Option<usize>,
-		safe: Option<bool>
+		safe: Option<bool>,
+		/// Keep the condition rolled after each step instead of resetting it to `Normal`
+		/// for steps without an entry in `step_states`.
+		random_conditions: Option<bool>,
 	) -> SimulationResult {
 		let linear = linear.unwrap_or(false);
 		let max_steps = max_steps.unwrap_or(usize::MAX);
 		let safe = safe.unwrap_or(false);
+		let random_conditions = random_conditions.unwrap_or(false);
 
 		self.last_possible_reclaim_step = None;
 		self.actions
@@ -178,76 +207,114 @@ impl Simulation {
 			.iter()
 			.enumerate()
 			.for_each(|(i, action)| {
-				self.state = self.step_states.get(i).map_or_else(
-					|| StepState::Normal,
-					|&s| {
-						if s == StepState::None {
-							StepState::Normal
-						} else {
-							s
-						}
-					},
-				);
-				let mut fail_cause: Option<FailCause> = None;
-
-				let can_use_action = action.can_be_used_with_flags(&self, Some(linear), Some(safe));
-				if !can_use_action {
-					fail_cause = action.get_fail_cause_with_flags(&self, Some(linear), Some(safe));
-				}
-				let has_enough_cp = action.get_base_cp_cost(&self) <= self.available_cp;
-				if !has_enough_cp {
-					fail_cause = Some(FailCause::NotEnoughCP);
-				}
-				// we can use the action
-				let mut result = if self.success.is_none()
-					&& has_enough_cp
-					&& self.steps.len() < max_steps
-					&& can_use_action
-				{
-					self.run_action_with_flags(action, linear, safe, i)
-				} else {
-					ActionResult {
-						action: *action,
-						success: None,
-						fail_cause,
-						added_progression: 0,
-						added_quality: 0,
-						cp_difference: 0,
-						solidity_difference: 0,
-						skipped: true,
-						combo: None,
-						state: self.state,
-						after_buff_tick: None,
-					}
-				};
-
-				if self.steps.len() < max_steps {
-					let quality_before = self.quality;
-					let progression_before = self.progression;
-					let durability_before = self.durability;
-					let cp_before = self.available_cp as i32;
-					let skip_ticks_on_fail =
-						!result.success.unwrap_or(false) && action.skip_on_fail();
-					if self.success.is_none() && !action.skips_buff_ticks() && !skip_ticks_on_fail {
-						self.tick_buffs(action);
-					}
-					result.after_buff_tick = Some(BuffTickResult {
-						added_progression: self.progression - progression_before,
-						added_quality: self.quality - quality_before,
-						cp_difference: self.available_cp as i32 - cp_before,
-						solidity_difference: self.durability - durability_before,
-					});
-				}
-
-				if !linear
-					&& *action != actions::FinalAppraisal.into()
-					&& *action != actions::RemoveFinalAppraisal.into()
-				{
+				self.set_step_state(i, random_conditions);
+				self.run_step(action, i, linear, safe, max_steps, None);
+				if !linear && Self::ticks_state(action) {
 					self.tick_state();
 				}
-				self.steps.push(result);
 			});
 
+		self.into_result()
+	}
+
+	// sets the condition for step `index`, either as given in `step_states` or as rolled
+	pub(crate) fn set_step_state(&mut self, index: usize, random_conditions: bool) {
+		self.state = match self.step_states.get(index) {
+			Some(&s) if s != StepState::None => s,
+			_ if random_conditions => self.state,
+			_ => StepState::Normal,
+		};
+	}
+
+	// whether step `index` was set to fail by the `fails` builder argument
+	pub(crate) fn is_forced_fail(&self, index: usize) -> bool {
+		self.fails.contains(&index)
+	}
+
+	// whether the condition changes after using `action`
+	pub(crate) fn ticks_state(action: &CraftingActionEnum) -> bool {
+		*action != actions::FinalAppraisal.into() && *action != actions::RemoveFinalAppraisal.into()
+	}
+
+	// runs a single step of the rotation and records it in `steps`, without changing the
+	// condition afterwards. `probability_roll` overrides the success roll, if given.
+	pub(crate) fn run_step(
+		&mut self,
+		action: &CraftingActionEnum,
+		index: usize,
+		linear: bool,
+		safe: bool,
+		max_steps: usize,
+		probability_roll: Option<u32>,
+	) -> ActionResult {
+		let mut fail_cause: Option<FailCause> = None;
+
+		let reclaim: CraftingActionEnum = actions::Reclaim.into();
+		if self.success.is_none()
+			&& self.steps.len() < max_steps
+			&& reclaim.can_be_used_with_flags(self, Some(linear), Some(safe))
+			&& reclaim.get_cp_cost(self) <= self.available_cp
+			&& reclaim.get_durability_cost(self) < self.durability.max(0) as u32
+		{
+			self.last_possible_reclaim_step = Some(index as u32);
+		}
+
+		let can_use_action = action.can_be_used_with_flags(self, Some(linear), Some(safe));
+		if !can_use_action {
+			fail_cause = action.get_fail_cause_with_flags(self, Some(linear), Some(safe));
+		}
+		let has_enough_cp = action.get_base_cp_cost(self) <= self.available_cp;
+		if !has_enough_cp {
+			fail_cause = Some(FailCause::NotEnoughCP);
+		}
+		// we can use the action
+		let mut result = if self.success.is_none()
+			&& has_enough_cp
+			&& self.steps.len() < max_steps
+			&& can_use_action
+		{
+			match probability_roll {
+				Some(roll) => self.run_action_with_roll(action, linear, safe, roll),
+				None => self.run_action_with_flags(action, linear, safe, index),
+			}
+		} else {
+			ActionResult {
+				action: *action,
+				success: None,
+				fail_cause,
+				added_progression: 0,
+				added_quality: 0,
+				cp_difference: 0,
+				solidity_difference: 0,
+				skipped: true,
+				combo: None,
+				state: self.state,
+				after_buff_tick: None,
+			}
+		};
+
+		if self.steps.len() < max_steps {
+			let quality_before = self.quality;
+			let progression_before = self.progression;
+			let durability_before = self.durability;
+			let cp_before = self.available_cp as i32;
+			let skip_ticks_on_fail = !result.success.unwrap_or(false) && action.skip_on_fail();
+			if self.success.is_none() && !action.skips_buff_ticks() && !skip_ticks_on_fail {
+				self.tick_buffs(action);
+			}
+			result.after_buff_tick = Some(BuffTickResult {
+				added_progression: self.progression - progression_before,
+				added_quality: self.quality - quality_before,
+				cp_difference: self.available_cp as i32 - cp_before,
+				solidity_difference: self.durability - durability_before,
+			});
+		}
+
+		self.steps.push(result.clone());
+		result
+	}
+
+	pub(crate) fn into_result(self) -> SimulationResult {
 		let failed_action = self
 			.steps
 			.iter()
@@ -260,8 +327,16 @@ impl Simulation {
 			} else {
 				true
 			};
+		// a failed craft yields no item, so it has no collectability
+		let collectable = self
+			.recipe
+			.collectable_thresholds
+			.as_ref()
+			.filter(|_| success)
+			.map(|thresholds| CollectableResult::new(self.quality, thresholds));
 		let mut res = SimulationResult {
 			steps: self.steps.clone(),
+			last_possible_reclaim_step: self.last_possible_reclaim_step,
 			hq_percent: self.get_hq_percent(),
 			success,
 			simulation: self,
@@ -270,6 +345,7 @@ impl Simulation {
 			} else {
 				None
 			},
+			collectable,
 		};
 		if let Some(failed_action) = failed_action {
 			if failed_action.fail_cause.is_some() {
@@ -304,8 +380,19 @@ impl Simulation {
 		} else if linear {
 			0
 		} else {
-			rand::thread_rng().gen_range(0..100)
+			self.rng.random_range(0..100)
 		};
+		self.run_action_with_roll(action, linear, safe, probability_roll)
+	}
+
+	// runs `action`, succeeding if its success rate is at least `probability_roll`
+	fn run_action_with_roll(
+		&mut self,
+		action: &CraftingActionEnum,
+		linear: bool,
+		safe: bool,
+		probability_roll: u32,
+	) -> ActionResult {
 		let quality_before = self.quality;
 		let progression_before = self.progression;
 		let durability_before = self.durability;
@@ -360,6 +447,15 @@ impl Simulation {
 		}
 	}
 
+	pub fn buffs(&self) -> &[EffectiveBuff] {
+		&self.buffs
+	}
+
+	pub fn has_trait(&self, crafter_trait: CrafterTrait) -> bool {
+		self.game_version.has_trait(crafter_trait)
+			&& tables::has_trait(self.crafter_stats.level, crafter_trait)
+	}
+
 	pub fn has_buff(&self, buff: Buff) -> bool {
 		self.buffs.iter().any(|x| x.buff == buff)
 	}
@@ -387,6 +483,11 @@ impl Simulation {
 		self.durability = (self.recipe.durability as i32).min(self.durability + (amt as i32));
 	}
 
+	/// The collectability of the item, which is a tenth of its quality.
+	pub fn get_collectability(&self) -> u32 {
+		self.quality / 10
+	}
+
 	pub fn get_hq_percent(&self) -> u32 {
 		let quality_percent =
 			(((self.quality as f64 / self.recipe.quality as f64) * 100.0).floor() as u32).min(100);
@@ -421,61 +522,61 @@ impl Simulation {
 			.collect();
 	}
 
-	pub fn possible_conditions(&self) -> &HashSet<StepState> {
-		&self.possible_conditions
+	pub fn possible_conditions(&self) -> ConditionSet {
+		self.possible_conditions
+	}
+
+	pub fn condition_rates(&self) -> &ConditionRates {
+		&self.condition_rates
 	}
 
 	pub fn tick_state(&mut self) {
+		let distribution = self.next_state_distribution();
+		self.state = match distribution.as_slice() {
+			[(state, _)] => *state,
+			_ => self
+				.get_weighted_random(distribution)
+				.unwrap_or(StepState::Normal),
+		};
+	}
+
+	/// The conditions that can follow the current one, with their probabilities, which add
+	/// up to 1.
+	pub fn next_state_distribution(&self) -> Vec<(StepState, f64)> {
 		// if current state is EXCELLENT, next is always POOR
 		if self.state == StepState::Excellent {
-			self.state = StepState::Poor;
-			return;
+			return vec![(StepState::Poor, 1.0)];
 		}
 		// if current state is GOOD OMEN, next is always GOOD
 		else if self.state == StepState::GoodOmen {
-			self.state = StepState::Good;
-			return;
+			return vec![(StepState::Good, 1.0)];
 		}
 
-		// Quality Assurance trait, level 63
-		let good_chance = if self.crafter_stats.level >= 63 {
-			0.25
-		} else {
-			0.2
-		};
-
-		let mut states_and_rates: HashMap<_, _> =
-			HashMap::from_iter(self.possible_conditions.iter().filter_map(|&step_state| {
-				match step_state {
-					StepState::Good => Some(if self.recipe.expert.is_some_and(|b| b) {
-						0.12
-					} else {
-						good_chance
-					}),
-					StepState::Excellent => Some(if self.recipe.expert.is_some_and(|b| b) {
-						0.0
-					} else {
-						0.04
-					}),
-					StepState::Poor => Some(0.0),
-					StepState::Centered => Some(0.15),
-					StepState::Sturdy => Some(0.15),
-					StepState::Pliant => Some(0.12),
-					StepState::Malleable => Some(0.12),
-					StepState::Primed => Some(0.12),
-					StepState::GoodOmen => Some(0.1),
-					_ => None,
-				}
-				.map(|rate| (step_state, rate))
-			}));
-		let non_normal_rate: f64 = states_and_rates.values().sum();
-		states_and_rates.insert(StepState::Normal, 1.0 - non_normal_rate);
-		self.state = Self::get_weighted_random(states_and_rates).unwrap_or(StepState::Normal);
+		let rates = self.condition_rates.for_recipe(&self.recipe);
+		let quality_assurance = self.has_trait(CrafterTrait::QualityAssurance);
+		let mut states_and_rates: Vec<_> = self
+			.possible_conditions
+			.iter()
+			.filter_map(|step_state| {
+				rates
+					.rate(step_state, quality_assurance)
+					.map(|rate| (step_state, rate.max(0.0)))
+			})
+			.collect();
+		let non_normal_rate: f64 = states_and_rates.iter().map(|&(_, rate)| rate).sum();
+		// rates adding up past 1 are scaled down, leaving no chance of Normal
+		if non_normal_rate > 1.0 {
+			states_and_rates
+				.iter_mut()
+				.for_each(|(_, rate)| *rate /= non_normal_rate);
+		}
+		states_and_rates.push((StepState::Normal, (1.0 - non_normal_rate).max(0.0)));
+		states_and_rates
 	}
 
-	fn get_weighted_random<T>(weighted_items: HashMap<T, f64>) -> Option<T> {
-		let total_weight: f64 = weighted_items.values().sum();
-		let threshold = random::<f64>() * total_weight;
+	fn get_weighted_random<T>(&mut self, weighted_items: Vec<(T, f64)>) -> Option<T> {
+		let total_weight: f64 = weighted_items.iter().map(|&(_, weight)| weight).sum();
+		let threshold = self.rng.random::<f64>() * total_weight;
 
 		let mut sum = 0.0;
 		for (item, weight) in weighted_items {
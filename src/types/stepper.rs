@@ -0,0 +1,115 @@
+use bon::bon;
+
+use crate::types::{
+	enums::CraftingActionEnum,
+	structs::{ActionResult, SimulationResult},
+	Simulation,
+};
+
+/// Runs a simulation one action at a time, keeping every earlier step so that it can be
+/// undone.
+///
+/// Each step is applied the same way [`Simulation::start`] applies it, so stepping through
+/// a rotation gives the same result as running it all at once with the same options.
+#[derive(Clone)]
+pub struct SimulationStepper {
+	simulation: Simulation,
+	// the simulation as it was before each step, oldest first
+	history: Vec<Simulation>,
+	linear: bool,
+	safe: bool,
+	random_conditions: bool,
+}
+impl SimulationStepper {
+	/// The simulation after the steps taken so far. Its state is the condition the next
+	/// action will be used in.
+	pub fn current(&self) -> &Simulation {
+		&self.simulation
+	}
+
+	/// The number of steps taken so far.
+	pub fn len(&self) -> usize {
+		self.simulation.steps.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.simulation.steps.is_empty()
+	}
+
+	/// Uses `action` as the next step, then advances the condition.
+	pub fn step(&mut self, action: CraftingActionEnum) -> ActionResult {
+		self.history.push(self.simulation.clone());
+		self.simulation
+			.step_action(action, self.linear, self.safe, self.random_conditions)
+	}
+
+	/// Undoes the last step, returning it, or `None` if no step was taken yet.
+	pub fn undo(&mut self) -> Option<ActionResult> {
+		let previous = self.history.pop()?;
+		let undone = std::mem::replace(&mut self.simulation, previous);
+		undone.steps.last().cloned()
+	}
+
+	/// Goes back to how the simulation was after its first `step` steps, undoing every later
+	/// step. Does nothing if fewer steps were taken.
+	pub fn rewind_to(&mut self, step: usize) {
+		if step < self.history.len() {
+			self.history.truncate(step + 1);
+			self.simulation = self.history.pop().expect("history holds the rewound step");
+		}
+	}
+
+	/// Finishes the run, returning its result as [`Simulation::start`] would.
+	pub fn into_result(self) -> SimulationResult {
+		self.simulation.into_result()
+	}
+}
+
+#[bon]
+impl Simulation {
+	/// Starts running this simulation one action at a time. Its `actions` are ignored, and
+	/// replaced by the actions that get stepped through.
+	#[builder(finish_fn = build)]
+	pub fn stepper(
+		mut self,
+		linear: Option<bool>,
+		safe: Option<bool>,
+		/// Keep the condition rolled after each step instead of resetting it to `Normal`
+		/// for steps without an entry in `step_states`.
+		random_conditions: Option<bool>,
+	) -> SimulationStepper {
+		let random_conditions = random_conditions.unwrap_or(false);
+		self.actions.clear();
+		self.set_step_state(0, random_conditions);
+
+		SimulationStepper {
+			simulation: self,
+			history: vec![],
+			linear: linear.unwrap_or(false),
+			safe: safe.unwrap_or(false),
+			random_conditions,
+		}
+	}
+}
+
+impl Simulation {
+	// appends `action` to the rotation and runs it as the next step, then advances the
+	// condition as `start` does between steps
+	pub(crate) fn step_action(
+		&mut self,
+		action: CraftingActionEnum,
+		linear: bool,
+		safe: bool,
+		random_conditions: bool,
+	) -> ActionResult {
+		let index = self.steps.len();
+		self.actions.push(action);
+		let result = self.run_step(&action, index, linear, safe, usize::MAX, None);
+		if !linear && Simulation::ticks_state(&action) {
+			self.tick_state();
+		}
+		self.set_step_state(index + 1, random_conditions);
+
+		result
+	}
+}
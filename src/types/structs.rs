@@ -1,7 +1,11 @@
+use bon::bon;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::ops::{Index, IndexMut};
-use super::{enums::*, Simulation};
+use super::{enums::*, tables, Simulation};
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct ActionResult {
 	pub action: CraftingActionEnum,
 	pub success: Option<bool>,
@@ -17,6 +21,7 @@ pub struct ActionResult {
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct BuffTickResult {
 	pub added_progression: u32,
 	pub added_quality: u32,
@@ -25,6 +30,7 @@ pub struct BuffTickResult {
 }
 
 #[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Craft {
 	pub id: String,
 	pub job: u32,
@@ -50,9 +56,145 @@ pub struct Craft {
 	pub progress_modifier: Option<f64>,
 	pub quality_modifier: Option<f64>,
 	pub required_quality: Option<u32>,
+	/// The collectability needed for each reward tier of a collectable, in ascending order.
+	pub collectable_thresholds: Option<Vec<u32>>,
+	/// The percentage of `quality` that HQ ingredients can provide at most. When set,
+	/// starting quality is computed from ingredient item levels as in game, instead of
+	/// from each ingredient's `quality`.
+	pub material_quality_factor: Option<u32>,
 }
 
-#[derive(Clone, Default)]
+impl Craft {
+	/// The quality a craft starts at when using `hq_ingredients`. Ingredients that aren't
+	/// part of the recipe add no quality.
+	///
+	/// With a `material_quality_factor`, HQ ingredients provide that share of the recipe's
+	/// quality, weighted by their item level against every ingredient that can be HQ.
+	/// Otherwise each HQ ingredient adds its `quality` per unit.
+	pub fn starting_quality(&self, hq_ingredients: &[Ingredient]) -> u32 {
+		let Some(material_quality_factor) = self.material_quality_factor else {
+			return hq_ingredients
+				.iter()
+				.filter_map(|hq_ingredient| {
+					self.find_ingredient(&hq_ingredient.id)
+						.map(|ingredient| ingredient.quality.unwrap_or(0) * hq_ingredient.amount)
+				})
+				.sum();
+		};
+
+		let max_item_levels: u64 = self
+			.ingredients
+			.iter()
+			.map(|ingredient| (ingredient.hq_item_level() * ingredient.amount) as u64)
+			.sum();
+		if max_item_levels == 0 {
+			return 0;
+		}
+		let provided_item_levels: u64 = hq_ingredients
+			.iter()
+			.filter_map(|hq_ingredient| {
+				self.find_ingredient(&hq_ingredient.id).map(|ingredient| {
+					let amount = hq_ingredient.amount.min(ingredient.amount);
+					(ingredient.hq_item_level() * amount) as u64
+				})
+			})
+			.sum();
+
+		(self.quality as u64 * material_quality_factor as u64 * provided_item_levels
+			/ max_item_levels
+			/ 100) as u32
+	}
+
+	// how much one HQ unit of the ingredient `id` weighs towards starting quality
+	pub(crate) fn hq_weight(&self, id: &str) -> u32 {
+		self.find_ingredient(id).map_or(0, |ingredient| {
+			if self.material_quality_factor.is_some() {
+				ingredient.hq_item_level()
+			} else {
+				ingredient.quality.unwrap_or(0)
+			}
+		})
+	}
+
+	fn find_ingredient(&self, id: &str) -> Option<&Ingredient> {
+		self.ingredients
+			.iter()
+			.find(|recipe_ingredient| recipe_ingredient.id == id)
+	}
+}
+
+#[bon]
+impl Craft {
+	/// A recipe of recipe level `rlvl`, from the embedded recipe level table (see
+	/// [`tables::recipe_level`]), or `None` if the table doesn't have that level.
+	///
+	/// The factors are percentages of the level's base progress, quality and durability,
+	/// and default to 100.
+	#[builder(finish_fn = build)]
+	pub fn from_recipe_level(
+		rlvl: u32,
+		progress_factor: Option<u32>,
+		quality_factor: Option<u32>,
+		durability_factor: Option<u32>,
+	) -> Option<Craft> {
+		tables::recipe_level(rlvl).map(|level| {
+			level.craft(
+				rlvl,
+				progress_factor.unwrap_or(100),
+				quality_factor.unwrap_or(100),
+				durability_factor.unwrap_or(100),
+			)
+		})
+	}
+}
+
+/// A row of the game's recipe level table, shared by every recipe of that recipe level.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct RecipeLevel {
+	pub level: CraftingLevel,
+	pub stars: u32,
+	pub suggested_craftsmanship: u32,
+	pub difficulty: u32,
+	pub quality: u32,
+	pub progress_divider: u32,
+	pub quality_divider: u32,
+	pub progress_modifier: u32,
+	pub quality_modifier: u32,
+	pub durability: u32,
+	pub conditions_flag: u32,
+}
+impl RecipeLevel {
+	/// A recipe of this level. Its progress, quality and durability are scaled by the
+	/// recipe's own factors, which are percentages.
+	pub fn craft(
+		&self,
+		rlvl: u32,
+		difficulty_factor: u32,
+		quality_factor: u32,
+		durability_factor: u32,
+	) -> Craft {
+		Craft {
+			rlvl,
+			lvl: self.level,
+			progress: self.difficulty * difficulty_factor / 100,
+			quality: self.quality * quality_factor / 100,
+			durability: self.durability * durability_factor / 100,
+			suggested_craftsmanship: (self.suggested_craftsmanship != 0)
+				.then_some(self.suggested_craftsmanship),
+			stars: Some(self.stars),
+			conditions_flag: self.conditions_flag,
+			progress_divider: self.progress_divider,
+			quality_divider: self.quality_divider,
+			progress_modifier: Some(self.progress_modifier as f64),
+			quality_modifier: Some(self.quality_modifier as f64),
+			..Default::default()
+		}
+	}
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct CrafterStats {
 	pub job_id: u32,
 	pub craftsmanship: u32,
@@ -64,7 +206,8 @@ pub struct CrafterStats {
 	pub levels: CrafterLevels,
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct CrafterLevels {
 	crp: CraftingLevel,
 	bsm: CraftingLevel,
@@ -148,7 +291,9 @@ impl IndexMut<CraftingJob> for CrafterLevels {
 
 const MAX_LEVEL: u8 = 100;
 
-#[derive(Clone, Copy, Default, Eq, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u8", into = "u8"))]
 pub struct CraftingLevel {
 	val: u8,
 }
@@ -173,7 +318,7 @@ impl TryFrom<u8> for CraftingLevel {
 	type Error = &'static str;
 
 	fn try_from(value: u8) -> Result<Self, Self::Error> {
-		CraftingLevel::new(value).ok_or("")
+		CraftingLevel::new(value).ok_or("crafting level is above the maximum level")
 	}
 }
 impl From<CraftingLevel> for u8 {
@@ -200,12 +345,16 @@ impl PartialOrd<u8> for CraftingLevel {
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(from = "SerializedEffectiveBuff"))]
 pub struct EffectiveBuff {
 	pub duration: i32,
 	pub stacks: u32,
 	pub buff: Buff,
 	pub applied_step: u32,
+	#[cfg_attr(feature = "serde", serde(skip))]
 	pub tick: Option<fn(&mut Simulation, &CraftingActionEnum) -> ()>,
+	#[cfg_attr(feature = "serde", serde(skip))]
 	pub on_expire: Option<fn(&mut Simulation, &CraftingActionEnum) -> ()>,
 }
 impl EffectiveBuff {
@@ -222,7 +371,34 @@ impl EffectiveBuff {
 	}
 }
 
-#[derive(Clone, Default)]
+// function pointers can't be serialized, so they are restored from the buff kind instead
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct SerializedEffectiveBuff {
+	duration: i32,
+	stacks: u32,
+	buff: Buff,
+	applied_step: u32,
+}
+#[cfg(feature = "serde")]
+impl From<SerializedEffectiveBuff> for EffectiveBuff {
+	fn from(value: SerializedEffectiveBuff) -> Self {
+		let action = super::actions::buff_action(value.buff);
+		let tick = action.and_then(|action| action.get_tick());
+		let on_expire = action.and_then(|action| action.get_on_expire());
+		Self {
+			duration: value.duration,
+			stacks: value.stacks,
+			buff: value.buff,
+			applied_step: value.applied_step,
+			tick,
+			on_expire,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Ingredient {
 	pub id: String,
 	pub amount: u32,
@@ -231,12 +407,59 @@ pub struct Ingredient {
 	pub part: Option<String>,
 	pub phase: Option<u32>,
 	pub custom: Option<bool>,
+	pub item_level: Option<u32>,
+	/// Whether the ingredient exists in HQ. Crystals, for example, don't.
+	pub can_be_hq: Option<bool>,
+}
+impl Ingredient {
+	// the item level this ingredient counts for when used in HQ, or 0 if it can't be
+	fn hq_item_level(&self) -> u32 {
+		if self.can_be_hq == Some(false) {
+			0
+		} else {
+			self.item_level.unwrap_or(0)
+		}
+	}
 }
 
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct SimulationResult {
 	pub steps: Vec<ActionResult>,
 	pub hq_percent: u32,
 	pub success: bool,
 	pub simulation: Simulation,
 	pub fail_cause: Option<FailCause>,
+	/// The reward tier reached by a successful craft, for recipes with `collectable_thresholds`.
+	pub collectable: Option<CollectableResult>,
+	/// The index of the last step at which Reclaim could still have been used, with
+	/// enough CP and durability left, or `None` if it could never be used.
+	#[cfg_attr(feature = "serde", serde(default))]
+	pub last_possible_reclaim_step: Option<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct CollectableResult {
+	pub collectability: u32,
+	/// The index of the highest threshold reached, or `None` if below every threshold.
+	pub tier: Option<usize>,
+	/// The quality still needed to reach the next tier, or `None` if the highest tier
+	/// was reached.
+	pub missing_quality: Option<u32>,
+}
+impl CollectableResult {
+	pub fn new(quality: u32, thresholds: &[u32]) -> Self {
+		let collectability = quality / 10;
+		let reached = thresholds
+			.iter()
+			.take_while(|&&threshold| collectability >= threshold)
+			.count();
+		Self {
+			collectability,
+			tier: reached.checked_sub(1),
+			missing_quality: thresholds
+				.get(reached)
+				.map(|&threshold| threshold * 10 - quality),
+		}
+	}
 }
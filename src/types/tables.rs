@@ -1,4 +1,7 @@
-use crate::types::structs::CraftingLevel;
+use crate::types::{
+	enums::CrafterTrait,
+	structs::{CraftingLevel, RecipeLevel},
+};
 
 pub const HQ_TABLE: [u32; 101] = [
 	1, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 6, 6, 6, 6, 7, 7, 7, 7, 8, 8, 8,
@@ -18,3 +21,69 @@ pub fn level_to_ilevel(lvl: CraftingLevel) -> u32 {
 
 	*arr.get::<usize>(Into::<u8>::into(lvl).into()).unwrap()
 }
+
+/// The level at which each crafter trait is learned, in ascending order.
+pub const TRAITS: [(u8, CrafterTrait); 8] = [
+	(11, CrafterTrait::InnerQuiet),
+	(31, CrafterTrait::BasicSynthesisMastery),
+	(63, CrafterTrait::RapidSynthesisMastery),
+	(63, CrafterTrait::QualityAssurance),
+	(82, CrafterTrait::CarefulSynthesisMastery),
+	(86, CrafterTrait::GroundworkMastery),
+	(94, CrafterTrait::DelicateSynthesisMastery),
+	(96, CrafterTrait::HastyTouchMastery),
+];
+
+/// Every trait a crafter of `level` has learned.
+pub fn traits(level: CraftingLevel) -> impl Iterator<Item = CrafterTrait> {
+	TRAITS
+		.into_iter()
+		.take_while(move |&(trait_level, _)| level >= trait_level)
+		.map(|(_, crafter_trait)| crafter_trait)
+}
+
+pub fn has_trait(level: CraftingLevel, crafter_trait: CrafterTrait) -> bool {
+	traits(level).any(|learned| learned == crafter_trait)
+}
+
+// recipe level, then the columns of the game's RecipeLevelTable sheet: ClassJobLevel, Stars,
+// SuggestedCraftsmanship, Difficulty, Quality, ProgressDivider, QualityDivider,
+// ProgressModifier, QualityModifier, Durability, ConditionsFlag
+const RECIPE_LEVELS: [(u32, [u32; 11]); 12] = [
+	(145, [58, 0, 0, 3000, 6700, 68, 48, 100, 100, 80, 15]),
+	(285, [68, 0, 0, 980, 3420, 88, 68, 100, 100, 80, 15]),
+	(480, [80, 0, 2480, 6178, 36208, 110, 90, 100, 100, 80, 15]),
+	(517, [81, 0, 0, 2000, 5200, 121, 105, 100, 100, 80, 15]),
+	(535, [85, 0, 0, 3000, 6700, 125, 109, 100, 100, 80, 15]),
+	(560, [90, 0, 0, 3500, 7200, 130, 115, 90, 80, 80, 15]),
+	(580, [90, 1, 0, 3900, 10920, 130, 115, 80, 70, 70, 15]),
+	(590, [90, 2, 0, 4300, 12800, 130, 115, 80, 70, 70, 15]),
+	(610, [90, 3, 0, 5060, 12628, 130, 115, 80, 70, 70, 15]),
+	(625, [90, 3, 0, 5280, 13050, 130, 115, 80, 70, 70, 15]),
+	(640, [90, 4, 0, 6600, 14040, 130, 115, 80, 70, 70, 15]),
+	(685, [99, 0, 0, 6300, 11400, 167, 147, 100, 100, 80, 15]),
+];
+
+/// The embedded row of the recipe level table for `rlvl`, or `None` if it isn't embedded.
+///
+/// Needs no feature. With the `csv` feature, `RecipeDatabase::recipe_level` reads the row of
+/// any recipe level from the game's sheets instead.
+pub fn recipe_level(rlvl: u32) -> Option<RecipeLevel> {
+	let index = RECIPE_LEVELS
+		.binary_search_by_key(&rlvl, |&(rlvl, _)| rlvl)
+		.ok()?;
+	let row = RECIPE_LEVELS[index].1;
+	Some(RecipeLevel {
+		level: CraftingLevel::unchecked_new(row[0] as u8),
+		stars: row[1],
+		suggested_craftsmanship: row[2],
+		difficulty: row[3],
+		quality: row[4],
+		progress_divider: row[5],
+		quality_divider: row[6],
+		progress_modifier: row[7],
+		quality_modifier: row[8],
+		durability: row[9],
+		conditions_flag: row[10],
+	})
+}
@@ -6,6 +6,7 @@ use crate::types::{
 };
 use dyn_clone::DynClone;
 use enum_dispatch::enum_dispatch;
+use rand::RngCore;
 
 #[enum_dispatch(CraftingActionEnum)]
 pub trait CraftingAction: DynClone {
@@ -35,6 +36,10 @@ pub trait CraftingAction: DynClone {
 
 	fn get_level_requirement(&self) -> (CraftingJob, CraftingLevel);
 
+	fn is_available_in(&self, _game_version: GameVersion) -> bool {
+		true
+	}
+
 	fn get_type(&self) -> ActionType;
 
 	fn _get_success_rate(&self, simulation_state: &Simulation) -> u32;
@@ -66,7 +71,8 @@ pub trait CraftingAction: DynClone {
 		let craftsmanship_requirement = simulation_state.recipe.craftsmanship_req;
 		let control_requirement = simulation_state.recipe.control_req;
 
-		(if safe.is_some_and(|b| b) && level_requirement.0 != CraftingJob::Any {
+		self.is_available_in(simulation_state.game_version)
+			&& (if safe.is_some_and(|b| b) && level_requirement.0 != CraftingJob::Any {
 			simulation_state.crafter_stats.levels[level_requirement.0] >= level_requirement.1
 		} else {
 			simulation_state.crafter_stats.craftsmanship
@@ -100,6 +106,8 @@ pub trait CraftingAction: DynClone {
 
 		if safe.is_some_and(|b| b) && self.get_success_rate(simulation_state) < 100 {
 			Some(FailCause::UnsafeAction)
+		} else if !self.is_available_in(simulation_state.game_version) {
+			Some(FailCause::UnavailableInGameVersion)
 		} else if (level_requirement.0 != CraftingJob::Any
 			&& simulation_state.crafter_stats.levels[level_requirement.0] < level_requirement.1)
 			|| simulation_state.crafter_stats.level < level_requirement.1
@@ -186,6 +194,14 @@ pub trait CraftingAction: DynClone {
 }
 dyn_clone::clone_trait_object!(CraftingAction);
 
+/// A source of randomness for success rolls and condition changes.
+///
+/// Implemented for every cloneable [`RngCore`], so any seeded generator from `rand` can be
+/// handed to [`Simulation::builder`].
+pub trait SimulationRng: RngCore + DynClone + Send {}
+impl<T: RngCore + Clone + Send> SimulationRng for T {}
+dyn_clone::clone_trait_object!(SimulationRng);
+
 pub trait GeneralAction: CraftingAction {
 	fn get_base_bonus(&self, _simulation_state: &Simulation) -> f64 {
 		1.0
@@ -387,7 +403,7 @@ impl CraftingAction for Class {
 		let efficiency = ((potency * buff_mod) as f32) as f64;
 		simulation_state.quality += (quality_increase * condition_mod * efficiency / 100.0) as u32;
 
-		if !skip_stack_addition && simulation_state.crafter_stats.level >= 11 {
+		if !skip_stack_addition && simulation_state.has_trait(CrafterTrait::InnerQuiet) {
 			simulation_state.add_inner_quiet_stacks(1);
 		}
 	}